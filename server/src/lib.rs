@@ -0,0 +1,5 @@
+//! The server as a library, so the app's sync tests can run it in their
+//! own process. `main.rs` just serves it on a port.
+
+pub mod routes;
+pub mod store;
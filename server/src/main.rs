@@ -0,0 +1,19 @@
+use std::env;
+use tokio::net::TcpListener;
+use todo_server::{ routes, store::Store };
+
+/// Where the server listens unless `TODO_SERVER_ADDR` says otherwise. The
+/// app expects it here.
+const DEFAULT_ADDR: &str = "127.0.0.1:3000";
+
+#[tokio::main]
+async fn main() {
+    let addr = env::var("TODO_SERVER_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.to_string());
+    let listener = TcpListener::bind(&addr).await.expect("the address should be free");
+
+    // the todos are only kept in memory, so they're gone when the server
+    // stops. The app still has its own copy in localStorage and sends it
+    // again.
+    println!("listening on http://{addr}");
+    axum::serve(listener, routes::app(Store::default())).await.expect("the server should keep running");
+}
@@ -0,0 +1,187 @@
+use axum::{
+    extract::{ Path, Query, State },
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use std::sync::{ Arc, Mutex };
+use tower_http::cors::CorsLayer;
+
+use crate::store::{ Changes, Record, Store, Write, WriteResult };
+
+type SharedStore = Arc<Mutex<Store>>;
+
+/// The REST api:
+///
+/// - `GET /todos?since=3` every change after revision 3, for syncing
+/// - `GET /lists/:list_id/todos` the todos of a list
+/// - `GET /lists/:list_id/todos/:id` one todo
+/// - `PUT /lists/:list_id/todos/:id` create or change a todo
+/// - `DELETE /lists/:list_id/todos/:id?base_revision=3&updated_at=...` delete one
+/// - `GET /data/:value?times=10` the value multiplied, for the Load Data page
+///
+/// The app is served from a different port in development, so any origin
+/// is allowed.
+pub fn app(store: Store) -> Router {
+    Router::new()
+        .route("/todos", get(changes))
+        .route("/lists/{list_id}/todos", get(list))
+        .route("/lists/{list_id}/todos/{id}", get(read).put(write).delete(delete))
+        .route("/data/{value}", get(data))
+        .layer(CorsLayer::permissive())
+        .with_state(Arc::new(Mutex::new(store)))
+}
+
+#[derive(Deserialize)]
+struct Since {
+    #[serde(default)]
+    since: u64,
+}
+
+async fn changes(State(store): State<SharedStore>, Query(Since { since }): Query<Since>) -> Json<Changes> {
+    Json(store.lock().unwrap().changes_since(since))
+}
+
+async fn list(State(store): State<SharedStore>, Path(list_id): Path<u64>) -> Json<Vec<Record>> {
+    Json(store.lock().unwrap().list(list_id))
+}
+
+async fn read(State(store): State<SharedStore>, Path((list_id, id)): Path<(u64, u64)>) -> Result<Json<Record>, StatusCode> {
+    store.lock().unwrap().get(list_id, id).cloned().map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn write(
+    State(store): State<SharedStore>,
+    Path((list_id, id)): Path<(u64, u64)>,
+    Json(write): Json<Write>,
+) -> Result<Json<WriteResult>, StatusCode> {
+    // deletes go through `DELETE` so a `PUT` always has a todo
+    if write.todo.is_none() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    Ok(Json(store.lock().unwrap().write(list_id, id, write)))
+}
+
+#[derive(Deserialize)]
+struct Delete {
+    #[serde(default)]
+    base_revision: u64,
+    updated_at: u64,
+}
+
+async fn delete(
+    State(store): State<SharedStore>,
+    Path((list_id, id)): Path<(u64, u64)>,
+    Query(Delete { base_revision, updated_at }): Query<Delete>,
+) -> Json<WriteResult> {
+    Json(store.lock().unwrap().write(list_id, id, Write { base_revision, updated_at, todo: None }))
+}
+
+#[derive(Deserialize)]
+struct Times {
+    times: i32,
+}
+
+async fn data(Path(value): Path<i32>, Query(Times { times }): Query<Times>) -> Json<i32> {
+    Json(value.saturating_mul(times))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{ body::{ to_bytes, Body }, http::Request };
+    use serde::de::DeserializeOwned;
+    use serde_json::{ json, Value };
+    use tower::ServiceExt;
+
+    /// Send one request to the app, running in this process.
+    async fn send<T: DeserializeOwned>(app: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Option<T>) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).ok())
+    }
+
+    fn put(base_revision: u64, updated_at: u64, title: &str) -> Option<Value> {
+        Some(json!({ "base_revision": base_revision, "updated_at": updated_at, "todo": { "title": title } }))
+    }
+
+    #[tokio::test]
+    async fn crud() {
+        let app = app(Store::default());
+
+        let (status, created) = send::<WriteResult>(&app, "PUT", "/lists/0/todos/1", put(0, 100, "A")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(created.unwrap().record.revision, 1);
+
+        let (_, record) = send::<Record>(&app, "GET", "/lists/0/todos/1", None).await;
+        assert_eq!(record.unwrap().todo, Some(json!({ "title": "A" })));
+
+        let (_, updated) = send::<WriteResult>(&app, "PUT", "/lists/0/todos/1", put(1, 200, "B")).await;
+        assert!(updated.unwrap().applied);
+
+        let (_, todos) = send::<Vec<Record>>(&app, "GET", "/lists/0/todos", None).await;
+        assert_eq!(todos.unwrap().len(), 1);
+
+        let (_, deleted) = send::<WriteResult>(&app, "DELETE", "/lists/0/todos/1?base_revision=2&updated_at=300", None).await;
+        assert!(deleted.unwrap().applied);
+
+        let (status, _) = send::<Record>(&app, "GET", "/lists/0/todos/1", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn conflicting_edits() {
+        let app = app(Store::default());
+        send::<WriteResult>(&app, "PUT", "/lists/0/todos/1", put(0, 100, "A")).await;
+
+        // a client that was offline replays an edit it made before the
+        // other client's, so it loses and gets the newer todo back
+        send::<WriteResult>(&app, "PUT", "/lists/0/todos/1", put(1, 300, "online")).await;
+        let (_, result) = send::<WriteResult>(&app, "PUT", "/lists/0/todos/1", put(1, 200, "offline")).await;
+        let result = result.unwrap();
+
+        assert!(!result.applied);
+        assert_eq!(result.record.todo, Some(json!({ "title": "online" })));
+    }
+
+    #[tokio::test]
+    async fn pull_changes() {
+        let app = app(Store::default());
+        send::<WriteResult>(&app, "PUT", "/lists/0/todos/1", put(0, 100, "A")).await;
+        send::<WriteResult>(&app, "PUT", "/lists/2/todos/1", put(0, 100, "B")).await;
+
+        let (_, changes) = send::<Changes>(&app, "GET", "/todos?since=1", None).await;
+        let changes = changes.unwrap();
+        assert_eq!(changes.revision, 2);
+        assert_eq!(changes.records.iter().map(|record| record.list_id).collect::<Vec<_>>(), vec![2]);
+
+        let (_, everything) = send::<Changes>(&app, "GET", "/todos", None).await;
+        assert_eq!(everything.unwrap().records.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn reject_put_without_a_todo() {
+        let app = app(Store::default());
+        let body = Some(json!({ "base_revision": 0, "updated_at": 100, "todo": null }));
+
+        let (status, _) = send::<WriteResult>(&app, "PUT", "/lists/0/todos/1", body).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn multiply_data() {
+        let app = app(Store::default());
+
+        let (status, value) = send::<i32>(&app, "GET", "/data/3?times=20", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(value, Some(60));
+    }
+}
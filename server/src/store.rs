@@ -0,0 +1,169 @@
+use serde::{ Deserialize, Serialize };
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// One todo as the server keeps it. The server never looks inside the
+/// todo, it's stored as whatever JSON the client sent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub list_id: u64,
+    pub id: u64,
+    /// Goes up with every write to any todo, so a client can ask for
+    /// everything written after the last revision it saw.
+    pub revision: u64,
+    /// When the client made the change, in milliseconds since 1970.
+    pub updated_at: u64,
+    /// `None` once the todo is deleted. The record is kept so other clients
+    /// find out about the delete.
+    pub todo: Option<Value>,
+}
+
+/// A change a client wants to make to one todo.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Write {
+    /// The revision of the todo the change was made to, or 0 if the client
+    /// has never seen it on the server.
+    pub base_revision: u64,
+    pub updated_at: u64,
+    /// `None` deletes the todo.
+    pub todo: Option<Value>,
+}
+
+/// What happened to a write.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WriteResult {
+    /// False if the write lost to a later edit from another client.
+    pub applied: bool,
+    /// The todo as it is now, whichever write won.
+    pub record: Record,
+}
+
+/// Every todo written since some revision.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Changes {
+    /// The latest revision. Asking for changes since this one next time
+    /// returns only what's new.
+    pub revision: u64,
+    pub records: Vec<Record>,
+}
+
+/// Every todo of every list, kept in memory. Todos are keyed by their list
+/// and their own id. The clients make ids that don't clash with each
+/// other's, so two todos made at once both get kept.
+#[derive(Debug, Default)]
+pub struct Store {
+    revision: u64,
+    records: BTreeMap<(u64, u64), Record>,
+}
+
+impl Store {
+    /// A todo that hasn't been deleted.
+    pub fn get(&self, list_id: u64, id: u64) -> Option<&Record> {
+        self.records.get(&(list_id, id)).filter(|record| record.todo.is_some())
+    }
+
+    /// The todos of one list that haven't been deleted.
+    pub fn list(&self, list_id: u64) -> Vec<Record> {
+        self.records.range((list_id, 0)..=(list_id, u64::MAX))
+            .map(|(_, record)| record)
+            .filter(|record| record.todo.is_some())
+            .cloned()
+            .collect()
+    }
+
+    /// Every todo written after `revision`, deletes included, oldest first.
+    pub fn changes_since(&self, revision: u64) -> Changes {
+        let mut records: Vec<Record> = self.records.values()
+            .filter(|record| record.revision > revision)
+            .cloned()
+            .collect();
+        records.sort_by_key(|record| record.revision);
+        Changes { revision: self.revision, records }
+    }
+
+    /// Create, change or delete a todo.
+    ///
+    /// If the todo has been written since the revision the client based its
+    /// change on, two clients changed it at once. The change that was made
+    /// last wins, whichever order they arrive in, so an edit that sat in an
+    /// offline queue doesn't overwrite a newer one. A tie goes to the write
+    /// that's already stored.
+    pub fn write(&mut self, list_id: u64, id: u64, write: Write) -> WriteResult {
+        let key = (list_id, id);
+        let applied = match self.records.get(&key) {
+            None => true,
+            Some(current) if current.revision == write.base_revision => true,
+            Some(current) => write.updated_at > current.updated_at,
+        };
+
+        if applied {
+            self.revision += 1;
+            let record = Record { list_id, id, revision: self.revision, updated_at: write.updated_at, todo: write.todo };
+            self.records.insert(key, record);
+        }
+        WriteResult { applied, record: self.records[&key].clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn put(base_revision: u64, updated_at: u64, title: &str) -> Write {
+        Write { base_revision, updated_at, todo: Some(json!({ "title": title })) }
+    }
+
+    #[test]
+    fn create_update_and_delete() {
+        let mut store = Store::default();
+
+        let created = store.write(0, 1, put(0, 100, "A"));
+        assert!(created.applied);
+        assert_eq!(created.record.revision, 1);
+
+        let updated = store.write(0, 1, put(1, 200, "B"));
+        assert!(updated.applied);
+        assert_eq!(store.get(0, 1).unwrap().todo, Some(json!({ "title": "B" })));
+
+        let deleted = store.write(0, 1, Write { base_revision: 2, updated_at: 300, todo: None });
+        assert!(deleted.applied);
+        assert!(store.get(0, 1).is_none());
+        assert!(store.list(0).is_empty());
+    }
+
+    #[test]
+    fn last_writer_wins() {
+        let mut store = Store::default();
+        store.write(0, 1, put(0, 100, "A"));
+
+        // two clients both edit revision 1. The second to arrive made its
+        // edit first, so it loses.
+        assert!(store.write(0, 1, put(1, 300, "newer")).applied);
+        let stale = store.write(0, 1, put(1, 200, "older"));
+        assert!(!stale.applied);
+        assert_eq!(stale.record.todo, Some(json!({ "title": "newer" })));
+
+        // and the other way round the later edit overwrites
+        assert!(store.write(0, 1, put(1, 400, "latest")).applied);
+        assert_eq!(store.get(0, 1).unwrap().revision, 3);
+    }
+
+    #[test]
+    fn changes_since_a_revision() {
+        let mut store = Store::default();
+        store.write(0, 1, put(0, 100, "A"));
+        store.write(0, 2, put(0, 100, "B"));
+        store.write(3, 1, put(0, 100, "C"));
+        store.write(0, 1, Write { base_revision: 1, updated_at: 200, todo: None });
+
+        let changes = store.changes_since(2);
+        let keys: Vec<(u64, u64, u64)> = changes.records.iter()
+            .map(|record| (record.list_id, record.id, record.revision))
+            .collect();
+
+        assert_eq!(changes.revision, 4);
+        assert_eq!(keys, vec![(3, 1, 3), (0, 1, 4)]);
+        assert_eq!(store.list(0).len(), 1);
+    }
+}
@@ -1,5 +1,151 @@
 use leptos::*;
+use gloo_net::http::Request;
 use gloo_timers::future::TimeoutFuture;
+use rand::Rng;
+use serde::{ Deserialize, Serialize };
+use std::{ fmt, future::Future, pin::Pin, rc::Rc };
+
+use crate::retry::{ create_retry_resource, RetryPolicy };
+use crate::query_cache::{ create_query, CacheOptions, QueryClient };
+
+/// A value a `DataService` is still loading.
+pub type Load<T> = Pin<Box<dyn Future<Output = Result<T, ServiceError>>>>;
+
+/// Why a `DataService` couldn't load a value. Like everything a resource
+/// holds it has to be serializable.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ServiceError {
+    /// The request never got an answer, e.g. because the server is down.
+    Network(String),
+    /// The service answered with an error status.
+    Status(u16),
+    /// The answer wasn't the data that was asked for.
+    Decode(String),
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::Network(reason) => write!(f, "Couldn't reach the service: {reason}"),
+            ServiceError::Status(status) => write!(f, "The service answered with status {status}."),
+            ServiceError::Decode(reason) => write!(f, "The service sent something unexpected: {reason}"),
+        }
+    }
+}
+
+// `ErrorBoundary` catches any `Result` whose error is a `std::error::Error`
+impl std::error::Error for ServiceError {}
+
+/// Where `AsyncLoad` and `LoadTwoServices` get their data from. The service
+/// is provided through the context, so the same components can run against
+/// the mock or a real server.
+pub trait DataService {
+    /// The number passed multiplied by ten.
+    fn load_data_10(&self, value: i32) -> Load<i32>;
+    /// The number passed multiplied by twenty.
+    fn load_data_20(&self, value: i32) -> Load<i32>;
+}
+
+/// The service the components find in the context.
+pub type Service = Rc<dyn DataService>;
+
+/// The service the app uses. It's the mock unless the app was built with
+/// `DATA_SERVICE_URL` set, e.g. `DATA_SERVICE_URL=http://127.0.0.1:3000
+/// trunk serve` to use the server in `server/`. The mock never fails unless
+/// the app was built with `MOCK_FAILURE_RATE` set, e.g. to `0.2` to see the
+/// error handling and retries.
+pub fn default_service() -> Service {
+    match option_env!("DATA_SERVICE_URL") {
+        Some(base_url) => Rc::new(HttpService::new(base_url)),
+        None => Rc::new(MockService::failing(mock_failure_rate(option_env!("MOCK_FAILURE_RATE")))),
+    }
+}
+
+/// The failure rate `MOCK_FAILURE_RATE` asks for. Anything that isn't a
+/// number from 0 to 1 counts as 0.
+fn mock_failure_rate(setting: Option<&str>) -> f64 {
+    setting
+        .and_then(|rate| rate.trim().parse().ok())
+        .filter(|rate| (0.0..=1.0).contains(rate))
+        .unwrap_or(0.0)
+}
+
+/// Mocks calling an external service and waiting for a response.
+#[derive(Default)]
+pub struct MockService {
+    /// The chance, from 0 to 1, that a request fails.
+    failure_rate: f64,
+}
+
+impl MockService {
+    /// A mock that fails `failure_rate` of the time, from 0 to 1.
+    pub fn failing(failure_rate: f64) -> MockService {
+        MockService { failure_rate }
+    }
+
+    /// Decide up front whether a request fails, so the future doesn't need
+    /// to hold on to the service. Like a real service it's sometimes quicker
+    /// and sometimes slower than `delay_ms`, so answers can come back in a
+    /// different order than they were asked for.
+    fn multiply_after(&self, value: i32, times: i32, delay_ms: u32) -> Load<i32> {
+        let fails = rand::random::<f64>() < self.failure_rate;
+        let delay_ms = rand::thread_rng().gen_range(delay_ms / 2..=delay_ms * 3 / 2);
+        Box::pin(async move {
+            TimeoutFuture::new(delay_ms).await;
+            // pretend the service is down
+            if fails { Err(ServiceError::Status(503)) } else { Ok(value * times) }
+        })
+    }
+}
+
+impl DataService for MockService {
+    /// Responds after a one second delay.
+    fn load_data_10(&self, value: i32) -> Load<i32> {
+        self.multiply_after(value, 10, 1_000)
+    }
+
+    /// Same as above except it takes longer to respond.
+    fn load_data_20(&self, value: i32) -> Load<i32> {
+        self.multiply_after(value, 20, 1_500)
+    }
+}
+
+/// Asks a server to do the multiplying, at `GET /data/:value?times=10`.
+pub struct HttpService {
+    base_url: String,
+}
+
+impl HttpService {
+    pub fn new(base_url: &str) -> HttpService {
+        HttpService { base_url: base_url.trim_end_matches('/').to_string() }
+    }
+
+    fn url(&self, value: i32, times: i32) -> String {
+        format!("{}/data/{value}?times={times}", self.base_url)
+    }
+
+    fn multiply(&self, value: i32, times: i32) -> Load<i32> {
+        let url = self.url(value, times);
+        Box::pin(async move {
+            let response = Request::get(&url).send().await
+                .map_err(|err| ServiceError::Network(err.to_string()))?;
+            if !response.ok() {
+                return Err(ServiceError::Status(response.status()));
+            }
+            response.json().await.map_err(|err| ServiceError::Decode(err.to_string()))
+        })
+    }
+}
+
+impl DataService for HttpService {
+    fn load_data_10(&self, value: i32) -> Load<i32> {
+        self.multiply(value, 10)
+    }
+
+    fn load_data_20(&self, value: i32) -> Load<i32> {
+        self.multiply(value, 20)
+    }
+}
 
 /// Mocks calling an external service and waiting for a response
 #[component]
@@ -7,16 +153,24 @@ pub fn AsyncLoad() -> impl IntoView {
     
     let (count, set_count) = create_signal(0);
 
-    // create_resource takes two arguments after its scope
-    let async_data = create_resource(
+    // the service is provided by `App`
+    let service = expect_context::<Service>();
+
+    // create_retry_resource works like create_resource, but a load that
+    // fails is tried again a few times before the error is shown. Clicking
+    // quickly starts a new load before the last one has answered, which
+    // cancels the old one so its answer is never shown.
+    let async_data = create_retry_resource(
         // This is the source signal. A future is created everytime it changes.
         move || count.get(),
         // This function makes the call to an external service when the signal changes.
-        |value| async move { load_data_10(value).await },
+        move |value| service.load_data_10(value),
+        RetryPolicy::default(),
     );
 
-    // Keep track of whether data has been loaded or is currently loading
-    let is_loading = move || if async_data.loading().get() { "Loading..." } else { "Idle." };
+    // Keep track of whether data has been loaded, is currently loading or
+    // is being retried
+    let is_loading = move || async_data.status("Idle.");
 
     view! {
         
@@ -30,10 +184,16 @@ pub fn AsyncLoad() -> impl IntoView {
 
         <p>"Raw count: " {move || count.get()}</p>
 
+        // the data is a `Result`. An error is caught by the `ErrorBoundary`,
+        // which shows the fallback instead of its children.
         {
             move || match async_data.get() {
                 None => view! { <p>"Loading..."</p> }.into_view(),
-                Some(data) => view! { <p>"Data from service: " {data}</p> }.into_view()
+                Some(data) => view! {
+                    <ErrorBoundary fallback=retry_fallback(async_data.resource())>
+                        <p>"Data from service: " {data}</p>
+                    </ErrorBoundary>
+                }.into_view()
             }
         }
 
@@ -43,21 +203,32 @@ pub fn AsyncLoad() -> impl IntoView {
 
 }
 
-/// This function mocks calling an external service.
-/// It returns a the number passed multiplied by ten 
-/// after a one second delay.
-async fn load_data_10(value: i32) -> i32 {
-    TimeoutFuture::new(1_000).await;
-    value * 10
+/// What `LoadTwoServices` asks the `DataService` for. It's the key its
+/// answers are cached under.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataQuery {
+    Times10(i32),
+    Times20(i32),
 }
 
-/// Same as function above except it takes longer to resolve
-/// and multiplies by 20.
-async fn load_data_20(value: i32) -> i32 {
-    TimeoutFuture::new(1_500).await;
-    value * 20
+impl DataQuery {
+    /// Ask `service` for the answer.
+    pub fn load(self, service: &dyn DataService) -> Load<i32> {
+        match self {
+            DataQuery::Times10(value) => service.load_data_10(value),
+            DataQuery::Times20(value) => service.load_data_20(value),
+        }
+    }
 }
 
+/// The cache `LoadTwoServices` loads through. It's provided through the
+/// context, so the answers outlive the page.
+pub type DataClient = QueryClient<DataQuery, Result<i32, ServiceError>>;
+
+/// Answers are loaded again after 10 seconds, and the 50 most recently used
+/// are kept.
+pub const DATA_CACHE: CacheOptions = CacheOptions { stale_ms: 10_000.0, capacity: 50 };
+
 /// Uses the Suspense tag to load two external services
 #[component]
 pub fn LoadTwoServices() -> impl IntoView {
@@ -65,17 +236,17 @@ pub fn LoadTwoServices() -> impl IntoView {
     let (count, set_count) = create_signal(0);
     let (count_2, set_count_2) = create_signal(0);
 
-    let data_a = create_resource(
-        move || count.get(),
-        |value| async move { load_data_10(value).await },
-    );
+    let service = expect_context::<Service>();
+    let client = expect_context::<DataClient>();
+
+    let fetch = move |query: DataQuery| query.load(&*service);
+
+    // a count that was loaded before is shown straight away from the cache
+    let data_a = create_query(client.clone(), move || DataQuery::Times10(count.get()), fetch.clone());
 
     let a_is_loading = move || if data_a.loading().get() { "Loading..." } else { "" };
 
-    let data_b = create_resource(
-        move || count_2.get(),
-        |value| async move { load_data_20(value).await },
-    );
+    let data_b = create_query(client, move || DataQuery::Times20(count_2.get()), fetch);
 
     let b_is_loading = move || if data_b.loading().get() { "Loading..." } else { "" };
 
@@ -94,14 +265,119 @@ pub fn LoadTwoServices() -> impl IntoView {
         <Transition
             fallback=move || view! { <p>"Loading..."</p> }
         >
+            // each resource has its own `ErrorBoundary`, so one failing
+            // doesn't hide the other and Retry only loads the one that failed
             {move || {
-                data_a.get().map(|data| view! {<p>"Data a: " {data} " " {a_is_loading}</p>})
+                data_a.get().map(|data| view! {
+                    <ErrorBoundary fallback=retry_fallback(data_a)>
+                        <p>"Data a: " {data} " " {a_is_loading}</p>
+                    </ErrorBoundary>
+                })
             }}
             {move || {
-                data_b.get().map(|data| view! {<p>"Data b: " {data} " " {b_is_loading}</p>})
+                data_b.get().map(|data| view! {
+                    <ErrorBoundary fallback=retry_fallback(data_b)>
+                        <p>"Data b: " {data} " " {b_is_loading}</p>
+                    </ErrorBoundary>
+                })
             }}
         </Transition>
 
     }
 
-}
\ No newline at end of file
+}
+
+/// The fallback of an `ErrorBoundary` around a resource: what went wrong
+/// and a button that loads the resource again.
+fn retry_fallback<S, T>(resource: Resource<S, T>) -> impl Fn(RwSignal<Errors>) -> View
+where
+    S: Clone + 'static,
+    T: 'static,
+{
+    move |errors| view! {
+        <div class="error">
+            {move || errors.with(|errors| {
+                errors.iter().map(|(_, err)| view! { <p>{err.to_string()}</p> }).collect_view()
+            })}
+            <button on:click=move |_| resource.refetch()>"Retry"</button>
+        </div>
+    }.into_view()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{ cell::Cell, task::{ Context, Poll, Waker } };
+
+    /// A service that answers straight away and counts what it's asked, so
+    /// whatever loads through it can be checked without waiting or random
+    /// failures.
+    #[derive(Default)]
+    struct FakeService {
+        down: bool,
+        requests: Cell<u32>,
+    }
+
+    impl FakeService {
+        fn answer(&self, value: i32) -> Load<i32> {
+            self.requests.set(self.requests.get() + 1);
+            let answer = if self.down { Err(ServiceError::Status(503)) } else { Ok(value) };
+            Box::pin(async move { answer })
+        }
+    }
+
+    impl DataService for FakeService {
+        fn load_data_10(&self, value: i32) -> Load<i32> {
+            self.answer(value * 10)
+        }
+
+        fn load_data_20(&self, value: i32) -> Load<i32> {
+            self.answer(value * 20)
+        }
+    }
+
+    fn answer(mut load: Load<i32>) -> Result<i32, ServiceError> {
+        match load.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(answer) => answer,
+            Poll::Pending => panic!("the fake answers straight away"),
+        }
+    }
+
+    #[test]
+    fn load_from_a_fake_service() {
+        let fake = Rc::new(FakeService::default());
+        // the same `Rc<dyn DataService>` the components find in the context
+        let service: Service = fake.clone();
+
+        assert_eq!(answer(DataQuery::Times10(3).load(&*service)), Ok(30));
+        assert_eq!(answer(DataQuery::Times20(3).load(&*service)), Ok(60));
+        assert_eq!(fake.requests.get(), 2);
+
+        let down = FakeService { down: true, ..FakeService::default() };
+        assert_eq!(answer(DataQuery::Times10(3).load(&down)), Err(ServiceError::Status(503)));
+    }
+
+    #[test]
+    fn http_service_urls() {
+        let service = HttpService::new("http://127.0.0.1:3000/");
+        assert_eq!(service.url(3, 10), "http://127.0.0.1:3000/data/3?times=10");
+        assert_eq!(service.url(-1, 20), "http://127.0.0.1:3000/data/-1?times=20");
+    }
+
+    #[test]
+    fn mock_failures_are_opt_in() {
+        assert_eq!(mock_failure_rate(None), 0.0);
+        assert_eq!(mock_failure_rate(Some("0.2")), 0.2);
+        assert_eq!(mock_failure_rate(Some("often")), 0.0);
+        assert_eq!(mock_failure_rate(Some("2")), 0.0);
+    }
+
+    #[test]
+    fn describe_errors() {
+        assert_eq!(ServiceError::Status(503).to_string(), "The service answered with status 503.");
+        assert_eq!(
+            ServiceError::Network("connection refused".to_string()).to_string(),
+            "Couldn't reach the service: connection refused",
+        );
+    }
+}
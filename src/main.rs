@@ -18,11 +18,14 @@ use parent::Parent;
 mod html_children;
 use html_children::WrapChildren;
 mod todo;
-use todo::ToDoApp;
+use todo::{ ToDoLists, SelectedList, Calendar, Stats };
+mod sequence;
+mod retry;
+mod query_cache;
 mod async_load;
-use async_load::{ AsyncLoad, LoadTwoServices };
+use async_load::{ AsyncLoad, LoadTwoServices, default_service, DataClient, DATA_CACHE };
 mod search;
-use search::SearchPage;
+use search::{ SearchPage, SearchClient, SEARCH_CACHE };
 
 fn main() {
     _ = console_log::init_with_level(log::Level::Debug);
@@ -33,6 +36,14 @@ fn main() {
 
 #[component]
 fn App() -> impl IntoView {
+
+    // the components that load data find the service to load it from in
+    // the context
+    provide_context(default_service());
+    // the caches live as long as the app, so their answers are still there
+    // when a page is visited again
+    provide_context(DataClient::new(DATA_CACHE));
+    provide_context(SearchClient::new(SEARCH_CACHE));
     
     view! {
         
@@ -67,7 +78,19 @@ fn App() -> impl IntoView {
                     <Route path="/lists" view=ListsPage/>
                     <Route path="/inputs" view=InputsPage/>
                     <Route path="/hierarchy" view=HierarchyPage/>
-                    <Route path="/todo" view=ToDoPage/>
+                    // Like the contacts below, each to-do list is a nested route. The
+                    // sidebar of lists stays put while the list next to it changes.
+                    <Route path="/todo" view=ToDoPage>
+                        // fixed paths are matched before the `:list_id` parameter
+                        <Route path="calendar" view=Calendar/>
+                        <Route path="stats" view=Stats/>
+                        <Route path=":list_id" view=SelectedList/>
+                        <Route path="" view=|| view! {
+                            <div class="select-list">
+                                "Select a list to see its to-dos."
+                            </div>
+                        }/>
+                    </Route>
                     <Route path="/load_data" view=LoadDataPage/>
                     // Routes can be nested to help Leptos decide what parts of a page to 
                     // render and re-render. For instance, this contacts list loads a list
@@ -230,7 +253,7 @@ fn ToDoPage() -> impl IntoView {
 
         <Title text="To Do"/>
 
-        <ToDoApp/>
+        <ToDoLists/>
         
     }
 }
@@ -0,0 +1,426 @@
+use leptos::*;
+use std::{
+    cell::RefCell,
+    future::Future,
+    mem,
+    pin::Pin,
+    rc::Rc,
+    task::{ Context, Poll, Waker },
+};
+
+/// How long answers stay fresh and how many are kept.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheOptions {
+    /// An answer older than this is still shown straight away, but it's
+    /// loaded again in the background.
+    pub stale_ms: f64,
+    /// The most keys kept. The least recently used are dropped first.
+    pub capacity: usize,
+}
+
+/// An answer a `QueryCache` can keep. Failed loads aren't kept, so the
+/// next time their key is asked for it's loaded again.
+pub trait Cacheable: Clone {
+    fn is_error(&self) -> bool {
+        false
+    }
+}
+
+impl<T: Clone, E: Clone> Cacheable for Result<T, E> {
+    fn is_error(&self) -> bool {
+        self.is_err()
+    }
+}
+
+impl Cacheable for String {}
+
+/// A load that's in progress. Everyone who asks for its key in the meantime
+/// waits for this one answer rather than starting their own load.
+pub struct Pending<V>(Rc<RefCell<PendingState<V>>>);
+
+struct PendingState<V> {
+    answer: Option<V>,
+    waiting: Vec<Waker>,
+}
+
+impl<V> Clone for Pending<V> {
+    fn clone(&self) -> Self {
+        Pending(self.0.clone())
+    }
+}
+
+impl<V: Clone> Pending<V> {
+    fn new() -> Pending<V> {
+        Pending(Rc::new(RefCell::new(PendingState { answer: None, waiting: Vec::new() })))
+    }
+
+    fn resolve(&self, answer: V) {
+        let waiting = {
+            let mut state = self.0.borrow_mut();
+            state.answer = Some(answer);
+            mem::take(&mut state.waiting)
+        };
+        waiting.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<V: Clone> Future for Pending<V> {
+    type Output = V;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<V> {
+        let mut state = self.0.borrow_mut();
+        match &state.answer {
+            Some(answer) => Poll::Ready(answer.clone()),
+            None => {
+                state.waiting.push(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// What a `QueryCache` has for a key.
+pub enum Lookup<V> {
+    /// An answer loaded less than `stale_ms` ago.
+    Fresh(V),
+    /// An older answer, which should be loaded again.
+    Stale(V),
+    /// No answer yet, but one is on its way.
+    Loading(Pending<V>),
+    Missing,
+}
+
+struct Entry<K, V> {
+    key: K,
+    /// The last answer kept and when it was loaded.
+    answer: Option<(V, f64)>,
+    loading: Option<Pending<V>>,
+    /// How many times the answer has been replaced by a newer one.
+    version: u64,
+}
+
+/// The answers kept for each key, from the least to the most recently used.
+/// Times are in milliseconds, from whatever clock the caller uses.
+pub struct QueryCache<K, V> {
+    entries: Vec<Entry<K, V>>,
+    options: CacheOptions,
+}
+
+impl<K: PartialEq, V: Cacheable> QueryCache<K, V> {
+    pub fn new(options: CacheOptions) -> QueryCache<K, V> {
+        QueryCache { entries: Vec::new(), options }
+    }
+
+    /// Look a key up and mark it as just used.
+    pub fn lookup(&mut self, key: &K, now: f64) -> Lookup<V> {
+        let Some(index) = self.entries.iter().position(|entry| entry.key == *key) else { return Lookup::Missing };
+        let entry = self.entries.remove(index);
+
+        let lookup = match (&entry.answer, &entry.loading) {
+            (Some((answer, loaded_at)), _) if now - loaded_at < self.options.stale_ms => Lookup::Fresh(answer.clone()),
+            (Some((answer, _)), _) => Lookup::Stale(answer.clone()),
+            (None, Some(pending)) => Lookup::Loading(pending.clone()),
+            (None, None) => Lookup::Missing,
+        };
+        self.entries.push(entry);
+        lookup
+    }
+
+    /// Note that a key is being loaded. Returns `None` if it already is,
+    /// so there's no need for a second load.
+    pub fn start(&mut self, key: K) -> Option<Pending<V>> {
+        let index = match self.entries.iter().position(|entry| entry.key == key) {
+            Some(index) if self.entries[index].loading.is_some() => return None,
+            Some(index) => index,
+            None => {
+                self.entries.push(Entry { key, answer: None, loading: None, version: 0 });
+                self.entries.len() - 1
+            }
+        };
+        let pending = Pending::new();
+        self.entries[index].loading = Some(pending.clone());
+        self.evict(index);
+        Some(pending)
+    }
+
+    /// Hand the answer to everyone waiting for it, and keep it unless the
+    /// load failed. Returns whether it was kept.
+    pub fn finish(&mut self, key: K, answer: V, now: f64) -> bool {
+        let index = match self.entries.iter().position(|entry| entry.key == key) {
+            Some(index) => index,
+            None => {
+                self.entries.push(Entry { key, answer: None, loading: None, version: 0 });
+                self.entries.len() - 1
+            }
+        };
+        let entry = &mut self.entries[index];
+        let keep = !answer.is_error();
+        if let Some(pending) = entry.loading.take() {
+            pending.resolve(answer.clone());
+        }
+        if keep {
+            if entry.answer.is_some() {
+                entry.version += 1;
+            }
+            entry.answer = Some((answer, now));
+        }
+        self.evict(index);
+        keep
+    }
+
+    /// The version of a key's answer. It goes up each time a reload replaces
+    /// the answer, but not when the first one arrives, since everyone who
+    /// asked for it is waiting on that load anyway.
+    pub fn version(&self, key: &K) -> u64 {
+        self.entries.iter().find(|entry| entry.key == *key).map_or(0, |entry| entry.version)
+    }
+
+    /// Drop the least recently used keys until there are no more than
+    /// `capacity`. Keys that are being loaded are kept until they finish,
+    /// and so is the entry at `keep`, the one just started or finished.
+    fn evict(&mut self, mut keep: usize) {
+        while self.entries.len() > self.options.capacity {
+            let unused = self.entries.iter().enumerate()
+                .position(|(index, entry)| index != keep && entry.loading.is_none());
+            let Some(index) = unused else { break };
+            self.entries.remove(index);
+            if index < keep {
+                keep -= 1;
+            }
+        }
+    }
+}
+
+/// A `QueryCache` shared by every component that asks for the same kind of
+/// keys. It's provided through the context.
+pub struct QueryClient<K: 'static, V: 'static> {
+    cache: Rc<RefCell<QueryCache<K, V>>>,
+    /// Notified whenever an answer is kept, so queries check whether theirs
+    /// has a new version.
+    kept: Trigger,
+}
+
+impl<K: 'static, V: 'static> Clone for QueryClient<K, V> {
+    fn clone(&self) -> Self {
+        QueryClient { cache: self.cache.clone(), kept: self.kept }
+    }
+}
+
+impl<K, V> QueryClient<K, V>
+where
+    K: PartialEq + Clone + 'static,
+    V: Cacheable + 'static,
+{
+    pub fn new(options: CacheOptions) -> QueryClient<K, V> {
+        QueryClient { cache: Rc::new(RefCell::new(QueryCache::new(options))), kept: create_trigger() }
+    }
+
+    /// The answer for a key. A fresh one is returned straight away. A stale
+    /// one is too, while it's loaded again in the background. Otherwise it
+    /// waits for the load, which is shared with anyone else asking for the
+    /// same key.
+    pub async fn load<Fu>(&self, key: K, fetch: Rc<impl Fn(K) -> Fu + 'static>) -> V
+    where
+        Fu: Future<Output = V> + 'static,
+    {
+        let lookup = self.cache.borrow_mut().lookup(&key, js_sys::Date::now());
+        match lookup {
+            Lookup::Fresh(answer) => answer,
+            Lookup::Stale(answer) => {
+                self.start(key, fetch);
+                answer
+            }
+            Lookup::Loading(pending) => pending.await,
+            Lookup::Missing => self.start(key, fetch).expect("a missing key isn't being loaded").await,
+        }
+    }
+
+    /// Load a key unless it's already loading. The load is spawned rather
+    /// than awaited, so it finishes for everyone waiting on it even if the
+    /// query that started it goes away.
+    fn start<Fu>(&self, key: K, fetch: Rc<impl Fn(K) -> Fu + 'static>) -> Option<Pending<V>>
+    where
+        Fu: Future<Output = V> + 'static,
+    {
+        let pending = self.cache.borrow_mut().start(key.clone())?;
+        let client = self.clone();
+        spawn_local(async move {
+            let answer = fetch(key.clone()).await;
+            if client.cache.borrow_mut().finish(key, answer, js_sys::Date::now()) {
+                client.kept.notify();
+            }
+        });
+        Some(pending)
+    }
+}
+
+/// Like `create_resource`, except the answers come from `client`. The key is
+/// the resource's source, so a key that was loaded before is shown again
+/// straight away. The source also holds the version of the key's answer, so
+/// the query loads again only when its own key is reloaded, not whenever
+/// any key is.
+pub fn create_query<K, V, Fu>(
+    client: QueryClient<K, V>,
+    key: impl Fn() -> K + 'static,
+    fetch: impl Fn(K) -> Fu + 'static,
+) -> Resource<(K, u64), V>
+where
+    K: PartialEq + Clone + 'static,
+    V: Cacheable + Serializable + 'static,
+    Fu: Future<Output = V> + 'static,
+{
+    let fetch = Rc::new(fetch);
+    let source = {
+        let client = client.clone();
+        create_memo(move |_| {
+            client.kept.track();
+            let key = key();
+            let version = client.cache.borrow().version(&key);
+            (key, version)
+        })
+    };
+    create_resource(
+        move || source.get(),
+        move |(key, _)| {
+            let client = client.clone();
+            let fetch = fetch.clone();
+            async move { client.load(key, fetch).await }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPTIONS: CacheOptions = CacheOptions { stale_ms: 1_000.0, capacity: 2 };
+
+    type Cache = QueryCache<&'static str, Result<i32, String>>;
+
+    fn answer(lookup: Lookup<Result<i32, String>>) -> Option<(&'static str, Result<i32, String>)> {
+        match lookup {
+            Lookup::Fresh(answer) => Some(("fresh", answer)),
+            Lookup::Stale(answer) => Some(("stale", answer)),
+            Lookup::Loading(_) | Lookup::Missing => None,
+        }
+    }
+
+    #[test]
+    fn stale_while_revalidate() {
+        let mut cache = Cache::new(OPTIONS);
+        assert!(matches!(cache.lookup(&"a", 0.0), Lookup::Missing));
+
+        cache.start("a").unwrap();
+        assert!(cache.finish("a", Ok(1), 0.0));
+        assert_eq!(answer(cache.lookup(&"a", 999.0)), Some(("fresh", Ok(1))));
+        assert_eq!(answer(cache.lookup(&"a", 1_000.0)), Some(("stale", Ok(1))));
+
+        // the stale answer is still shown while it's loaded again
+        cache.start("a").unwrap();
+        assert_eq!(answer(cache.lookup(&"a", 1_500.0)), Some(("stale", Ok(1))));
+        cache.finish("a", Ok(2), 1_500.0);
+        assert_eq!(answer(cache.lookup(&"a", 1_500.0)), Some(("fresh", Ok(2))));
+    }
+
+    #[test]
+    fn share_one_load() {
+        let mut cache = Cache::new(OPTIONS);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut first = cache.start("a").unwrap();
+        assert!(cache.start("a").is_none());
+        let Lookup::Loading(mut second) = cache.lookup(&"a", 0.0) else { panic!("a is loading") };
+        assert_eq!(Pin::new(&mut second).poll(&mut cx), Poll::Pending);
+
+        cache.finish("a", Ok(1), 0.0);
+        assert_eq!(Pin::new(&mut first).poll(&mut cx), Poll::Ready(Ok(1)));
+        assert_eq!(Pin::new(&mut second).poll(&mut cx), Poll::Ready(Ok(1)));
+    }
+
+    #[test]
+    fn failed_loads_are_not_kept() {
+        let mut cache = Cache::new(OPTIONS);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut pending = cache.start("a").unwrap();
+        assert!(!cache.finish("a", Err("down".to_string()), 0.0));
+        // the one waiting still gets the error
+        assert_eq!(Pin::new(&mut pending).poll(&mut cx), Poll::Ready(Err("down".to_string())));
+        assert!(matches!(cache.lookup(&"a", 0.0), Lookup::Missing));
+
+        // and a failed reload leaves the last good answer
+        cache.start("a").unwrap();
+        cache.finish("a", Ok(1), 0.0);
+        cache.start("a").unwrap();
+        cache.finish("a", Err("down".to_string()), 2_000.0);
+        assert_eq!(answer(cache.lookup(&"a", 2_000.0)), Some(("stale", Ok(1))));
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let mut cache = Cache::new(OPTIONS);
+        for (key, value) in [("a", 1), ("b", 2)] {
+            cache.start(key).unwrap();
+            cache.finish(key, Ok(value), 0.0);
+        }
+
+        // using "a" leaves "b" as the least recently used
+        cache.lookup(&"a", 0.0);
+        cache.start("c").unwrap();
+        cache.finish("c", Ok(3), 0.0);
+
+        assert!(matches!(cache.lookup(&"b", 0.0), Lookup::Missing));
+        assert_eq!(answer(cache.lookup(&"a", 0.0)), Some(("fresh", Ok(1))));
+        assert_eq!(answer(cache.lookup(&"c", 0.0)), Some(("fresh", Ok(3))));
+    }
+
+    #[test]
+    fn new_versions_replace_kept_answers() {
+        let mut cache = Cache::new(OPTIONS);
+        cache.start("a").unwrap();
+        cache.finish("a", Ok(1), 0.0);
+        cache.start("b").unwrap();
+        cache.finish("b", Ok(2), 0.0);
+        assert_eq!(cache.version(&"a"), 0);
+
+        // reloading "a" doesn't touch "b", so only queries for "a" load again
+        cache.start("a").unwrap();
+        cache.finish("a", Ok(3), 2_000.0);
+        assert_eq!(cache.version(&"a"), 1);
+        assert_eq!(cache.version(&"b"), 0);
+
+        // a failed reload leaves the answer as it was
+        cache.start("a").unwrap();
+        cache.finish("a", Err("down".to_string()), 2_000.0);
+        assert_eq!(cache.version(&"a"), 1);
+    }
+
+    #[test]
+    fn keep_keys_that_are_loading() {
+        let mut cache = Cache::new(OPTIONS);
+        cache.start("a").unwrap();
+        cache.start("b").unwrap();
+        cache.start("c").unwrap();
+
+        // nothing can be dropped until a load finishes
+        assert!(matches!(cache.lookup(&"a", 0.0), Lookup::Loading(_)));
+        assert!(matches!(cache.lookup(&"c", 0.0), Lookup::Loading(_)));
+    }
+
+    #[test]
+    fn keep_the_answer_that_just_arrived() {
+        let mut cache = Cache::new(OPTIONS);
+        for key in ["a", "b", "c"] {
+            cache.start(key).unwrap();
+        }
+
+        // "a" and "c" are still loading, but "b" isn't thrown away for them
+        cache.finish("b", Ok(2), 0.0);
+        assert_eq!(answer(cache.lookup(&"b", 0.0)), Some(("fresh", Ok(2))));
+
+        // once "a" arrives there's an older answer to drop
+        cache.finish("a", Ok(1), 0.0);
+        assert!(matches!(cache.lookup(&"b", 0.0), Lookup::Missing));
+        assert_eq!(answer(cache.lookup(&"a", 0.0)), Some(("fresh", Ok(1))));
+    }
+}
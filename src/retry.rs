@@ -0,0 +1,222 @@
+use leptos::*;
+use gloo_timers::future::TimeoutFuture;
+use std::{ future::Future, rc::Rc };
+
+use crate::sequence::Sequence;
+
+/// How a failed load is retried. Each wait is twice as long as the one
+/// before, up to `max_delay_ms`, and shortened by a random amount so
+/// clients that failed together don't all retry at the same moment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// How many times to try, counting the first.
+    pub max_attempts: u32,
+    /// Give up rather than wait past this long after the first attempt.
+    pub max_elapsed_ms: u32,
+    /// The wait after the first attempt fails.
+    pub base_delay_ms: u32,
+    pub max_delay_ms: u32,
+    /// How much of a wait can be cut off at random, from 0 to 1.
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            max_elapsed_ms: 15_000,
+            base_delay_ms: 500,
+            max_delay_ms: 4_000,
+            jitter: 0.5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// How long to wait after attempt number `attempt` fails. `random` is
+    /// a number from 0 to 1 that decides how much jitter is taken off.
+    pub fn delay_ms(&self, attempt: u32, random: f64) -> u32 {
+        let backoff = self.base_delay_ms
+            .saturating_mul(2_u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay_ms);
+        (backoff as f64 * (1.0 - self.jitter * random)) as u32
+    }
+
+    /// Whether to try again after attempt number `attempt` failed,
+    /// `elapsed_ms` after the first one started, if the wait would be
+    /// `delay_ms`.
+    pub fn should_retry(&self, attempt: u32, elapsed_ms: f64, delay_ms: u32) -> bool {
+        attempt < self.max_attempts && elapsed_ms + delay_ms as f64 <= self.max_elapsed_ms as f64
+    }
+
+    /// What to show while attempt number `attempt` is loading.
+    pub fn status(&self, attempt: u32) -> String {
+        if attempt <= 1 {
+            "Loading...".to_string()
+        } else {
+            format!("retrying (attempt {attempt}/{})…", self.max_attempts)
+        }
+    }
+}
+
+/// Whether a request the server answered with `status` is worth sending
+/// again. Most 4xx statuses say the request itself is wrong, so it would
+/// only get the same answer. Timeouts and rate limits are the exceptions.
+pub fn is_retryable(status: u16) -> bool {
+    !(400..500).contains(&status) || status == 408 || status == 429
+}
+
+/// Call `load` until it succeeds or `policy` gives up, in which case the
+/// last error is returned. `on_attempt` is told the number of each attempt
+/// as it starts.
+pub async fn retry<T, E, Fu>(policy: RetryPolicy, on_attempt: impl Fn(u32), load: impl Fn() -> Fu) -> Result<T, E>
+where
+    Fu: Future<Output = Result<T, E>>,
+{
+    let started = js_sys::Date::now();
+    let mut attempt = 1;
+    loop {
+        on_attempt(attempt);
+        let err = match load().await {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+
+        let delay_ms = policy.delay_ms(attempt, rand::random());
+        if !policy.should_retry(attempt, js_sys::Date::now() - started, delay_ms) {
+            return Err(err);
+        }
+        TimeoutFuture::new(delay_ms).await;
+        attempt += 1;
+    }
+}
+
+/// A resource whose failed loads are retried. Retrying makes a load take
+/// longer, so loads are also numbered with a `Sequence`: one that's
+/// superseded by a newer load is cancelled, along with its retries.
+pub struct RetryResource<S: 'static, T: 'static, E: 'static> {
+    resource: Resource<S, Option<Result<T, E>>>,
+    /// The answer to the latest load that wasn't cancelled.
+    latest: Memo<Option<Result<T, E>>>,
+    /// The attempt the current load is on.
+    attempt: ReadSignal<u32>,
+    policy: RetryPolicy,
+}
+
+impl<S: 'static, T: 'static, E: 'static> Clone for RetryResource<S, T, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S: 'static, T: 'static, E: 'static> Copy for RetryResource<S, T, E> {}
+
+/// Like `create_resource`, except that a load that fails is retried
+/// according to `policy`.
+pub fn create_retry_resource<S, T, E, Fu>(
+    source: impl Fn() -> S + 'static,
+    fetcher: impl Fn(S) -> Fu + 'static,
+    policy: RetryPolicy,
+) -> RetryResource<S, T, E>
+where
+    S: PartialEq + Clone + 'static,
+    T: PartialEq + Clone + 'static,
+    E: PartialEq + Clone + 'static,
+    Option<Result<T, E>>: Serializable,
+    Fu: Future<Output = Result<T, E>> + 'static,
+{
+    let (attempt, set_attempt) = create_signal(0);
+    let requests = Sequence::default();
+    let fetcher = Rc::new(fetcher);
+
+    let resource = create_resource(source, move |source: S| {
+        let fetcher = fetcher.clone();
+        requests.start(async move {
+            let on_attempt = |attempt| { _ = set_attempt.try_set(attempt); };
+            retry(policy, on_attempt, || fetcher(source.clone())).await
+        })
+    });
+
+    // a cancelled load resolves to `None` and leaves the last answer in
+    // place, so an older answer can never replace a newer one
+    let latest = create_memo(move |previous: Option<&Option<Result<T, E>>>| {
+        resource.get().flatten().or_else(|| previous.cloned().flatten())
+    });
+
+    RetryResource { resource, latest, attempt, policy }
+}
+
+impl<S, T, E> RetryResource<S, T, E>
+where
+    S: Clone + 'static,
+    T: Clone + 'static,
+    E: Clone + 'static,
+{
+    /// The latest answer, or `None` until there is one.
+    pub fn get(&self) -> Option<Result<T, E>> {
+        self.latest.get()
+    }
+
+    /// "Loading...", "retrying (attempt 2/5)…" while a load is going on, or
+    /// `idle` when there isn't one.
+    pub fn status(&self, idle: &str) -> String {
+        if self.resource.loading().get() {
+            self.policy.status(self.attempt.get())
+        } else {
+            idle.to_string()
+        }
+    }
+
+    /// The resource underneath, e.g. to `refetch` it.
+    pub fn resource(&self) -> Resource<S, Option<Result<T, E>>> {
+        self.resource
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        max_attempts: 5,
+        max_elapsed_ms: 10_000,
+        base_delay_ms: 500,
+        max_delay_ms: 3_000,
+        jitter: 0.5,
+    };
+
+    #[test]
+    fn back_off_exponentially() {
+        let delays: Vec<u32> = (1..=5).map(|attempt| POLICY.delay_ms(attempt, 0.0)).collect();
+        assert_eq!(delays, vec![500, 1_000, 2_000, 3_000, 3_000]);
+
+        // jitter takes off up to half the wait
+        assert_eq!(POLICY.delay_ms(2, 0.5), 750);
+        assert_eq!(POLICY.delay_ms(2, 1.0), 500);
+        assert_eq!(POLICY.delay_ms(40, 0.0), 3_000);
+    }
+
+    #[test]
+    fn give_up() {
+        assert!(POLICY.should_retry(1, 0.0, 500));
+        assert!(POLICY.should_retry(4, 5_000.0, 3_000));
+        // out of attempts
+        assert!(!POLICY.should_retry(5, 0.0, 500));
+        // the wait would go past the time limit
+        assert!(!POLICY.should_retry(2, 8_000.0, 3_000));
+    }
+
+    #[test]
+    fn retry_server_errors_but_not_bad_requests() {
+        assert!(is_retryable(503));
+        assert!(is_retryable(429));
+        assert!(!is_retryable(404));
+        assert!(!is_retryable(422));
+    }
+
+    #[test]
+    fn describe_attempts() {
+        assert_eq!(POLICY.status(1), "Loading...");
+        assert_eq!(POLICY.status(2), "retrying (attempt 2/5)…");
+    }
+}
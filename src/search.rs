@@ -2,6 +2,15 @@ use leptos::*;
 use leptos_router::*;
 use leptos_meta::*;
 
+use crate::query_cache::{ create_query, CacheOptions, QueryClient };
+
+/// The cache `SearchPage` loads its results through, keyed by the search.
+pub type SearchClient = QueryClient<String, String>;
+
+/// Results are searched for again after a minute, and the last 20 searches
+/// are kept.
+pub const SEARCH_CACHE: CacheOptions = CacheOptions { stale_ms: 60_000.0, capacity: 20 };
+
 async fn fetch_results(search_query: String) -> String {
     if search_query.is_empty() {
         String::new()
@@ -18,8 +27,9 @@ pub fn SearchPage() -> impl IntoView {
     // search stored as ?q=
     // let search = move || query.get("q").cloned().unwrap_or_default();
     let search = move || query.with(|query| query.get("q").cloned().unwrap_or_default());
-    // a resource driven by the search string
-    let search_results = create_resource(search, fetch_results);
+    // a resource driven by the search string. Going back to an earlier
+    // search shows its results from the cache.
+    let search_results = create_query(expect_context::<SearchClient>(), search, fetch_results);
 
     view! {
         
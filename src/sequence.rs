@@ -0,0 +1,161 @@
+use std::{
+    cell::{ Cell, RefCell },
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{ Context, Poll, Waker },
+};
+
+/// Numbers the requests made for a resource so only the answer to the
+/// latest one is used. Starting a request supersedes every request started
+/// before it: they're dropped, which cancels whatever they were waiting
+/// on, and resolve to `None` straight away.
+#[derive(Clone, Default)]
+pub struct Sequence(Rc<SequenceState>);
+
+#[derive(Default)]
+struct SequenceState {
+    latest: Cell<u64>,
+    /// The requests still in flight, woken when they're superseded so they
+    /// don't wait for an answer nobody wants.
+    waiting: RefCell<Vec<Waker>>,
+}
+
+impl Sequence {
+    pub fn start<F: Future>(&self, request: F) -> Latest<F> {
+        let number = self.0.latest.get() + 1;
+        self.0.latest.set(number);
+        for waker in self.0.waiting.take() {
+            waker.wake();
+        }
+        Latest { request: Some(Box::pin(request)), number, sequence: self.clone() }
+    }
+}
+
+/// A request started by `Sequence::start`. It resolves to `None` if a later
+/// one was started before it finished.
+pub struct Latest<F> {
+    request: Option<Pin<Box<F>>>,
+    number: u64,
+    sequence: Sequence,
+}
+
+impl<F: Future> Future for Latest<F> {
+    type Output = Option<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.number != self.sequence.0.latest.get() {
+            self.request = None;
+            return Poll::Ready(None);
+        }
+        let Some(request) = self.request.as_mut() else { return Poll::Ready(None) };
+
+        match request.as_mut().poll(cx) {
+            Poll::Ready(answer) => {
+                self.request = None;
+                Poll::Ready(Some(answer))
+            }
+            Poll::Pending => {
+                let mut waiting = self.sequence.0.waiting.borrow_mut();
+                if !waiting.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    waiting.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{ rngs::StdRng, Rng, SeedableRng };
+
+    /// A mock request that answers after being polled a number of times,
+    /// the way a real one answers after some time. It counts the times it's
+    /// dropped before answering.
+    struct Delayed {
+        value: u32,
+        polls: u32,
+        cancelled: Rc<Cell<u32>>,
+    }
+
+    impl Future for Delayed {
+        type Output = u32;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> {
+            if self.polls == 0 {
+                return Poll::Ready(self.value);
+            }
+            self.polls -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    impl Drop for Delayed {
+        fn drop(&mut self) {
+            if self.polls > 0 {
+                self.cancelled.set(self.cancelled.get() + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn only_the_latest_answer_is_shown() {
+        const REQUESTS: u32 = 10;
+        let cancelled = Rc::new(Cell::new(0));
+
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let sequence = Sequence::default();
+            let mut cx = Context::from_waker(Waker::noop());
+
+            let mut in_flight: Vec<Pin<Box<Latest<Delayed>>>> = Vec::new();
+            let mut started = 0;
+            // what the page shows. Answers from superseded requests are
+            // `None` and leave it as it was, like the memo in `AsyncLoad`.
+            let mut shown = None;
+
+            while started < REQUESTS || !in_flight.is_empty() {
+                // clicks come in at random while earlier requests, each
+                // with its own latency, are still loading
+                if started < REQUESTS && rng.gen_bool(0.3) {
+                    started += 1;
+                    let request = Delayed { value: started, polls: rng.gen_range(0..20), cancelled: cancelled.clone() };
+                    in_flight.push(Box::pin(sequence.start(request)));
+                }
+
+                in_flight.retain_mut(|request| match request.as_mut().poll(&mut cx) {
+                    Poll::Ready(answer) => {
+                        if let Some(value) = answer {
+                            assert!(shown < Some(value), "seed {seed}: {value} was shown after {shown:?}");
+                            shown = Some(value);
+                        }
+                        false
+                    }
+                    Poll::Pending => true,
+                });
+            }
+
+            assert_eq!(shown, Some(REQUESTS), "seed {seed}");
+        }
+        // the latencies overlap often enough that some requests are cut short
+        assert!(cancelled.get() > 0);
+    }
+
+    #[test]
+    fn superseded_requests_are_cancelled() {
+        let sequence = Sequence::default();
+        let cancelled = Rc::new(Cell::new(0));
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut first = Box::pin(sequence.start(Delayed { value: 1, polls: 5, cancelled: cancelled.clone() }));
+        assert_eq!(first.as_mut().poll(&mut cx), Poll::Pending);
+
+        let mut second = Box::pin(sequence.start(Delayed { value: 2, polls: 0, cancelled: cancelled.clone() }));
+        assert_eq!(first.as_mut().poll(&mut cx), Poll::Ready(None));
+        assert_eq!(cancelled.get(), 1);
+        assert_eq!(second.as_mut().poll(&mut cx), Poll::Ready(Some(2)));
+    }
+}
@@ -1,90 +1,2082 @@
 use leptos::*;
+use leptos_router::*;
+use crate::progressbar::ProgressBar;
+use serde::{ Deserialize, Serialize };
+use std::{ cmp::Reverse, collections::{ BTreeMap, BTreeSet, HashMap }, fmt, mem, str::FromStr };
+use gloo_timers::callback::Interval;
+use wasm_bindgen_futures::JsFuture;
+
+mod date;
+use date::{ Date, Weekday };
+mod action;
+use action::ToDoAction;
+mod calendar;
+pub use calendar::Calendar;
+mod history;
+use history::History;
+mod lists;
+use lists::{ Lists, NameError };
+mod pomodoro;
+use pomodoro::Pomodoro;
+mod recurrence;
+use recurrence::Recurrence;
+mod selection;
+use selection::Selection;
+mod shortcuts;
+use shortcuts::{ Command, SHORTCUTS };
+mod stats;
+pub use stats::Stats;
+mod storage;
+mod sync;
+use sync::SyncStatus;
+mod todotxt;
+
+/// How many changes can be undone.
+const HISTORY_LIMIT: usize = 100;
+
+/// A sidebar of named lists with buttons to add, rename and delete them.
+/// The list picked in the url (`/todo/:list_id`) is rendered next to it by
+/// the nested route, through `<Outlet/>`.
+#[component]
+pub fn ToDoLists() -> impl IntoView {
+
+    // every list lives in one signal and is saved to localStorage together
+    let (lists, set_lists) = create_signal(storage::load());
+    create_effect(move |_| lists.with(storage::save));
+
+    // nested routes can't be given props, so they find the lists in the
+    // context instead
+    provide_context(ListsState { lists, set_lists });
+
+    // `store_value` makes the navigate function `Copy` so the buttons of
+    // every row can use it
+    let navigate = store_value(use_navigate());
+    let location = use_location();
+
+    let name_element: NodeRef<html::Input> = create_node_ref();
+
+    // the reason the last new name was rejected, if it was
+    let (error, set_error) = create_signal(None::<NameError>);
+
+    let on_new_list = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+
+        let input = name_element.get().expect("<input> should be mounted");
+        let id = sync::new_id();
+        let mut result = Err(NameError::Empty);
+        set_lists.update(|lists| result = lists.new_list(id, input.value()));
+
+        match result {
+            Ok(()) => {
+                input.set_value("");
+                set_error.set(None);
+                navigate.with_value(|navigate| navigate(&format!("/todo/{id}"), Default::default()));
+            }
+            Err(err) => set_error.set(Some(err)),
+        }
+    };
+
+    let rename = move |id: u64| {
+        let name = lists.with_untracked(|lists| lists.find(id).map(|list| list.name.clone())).unwrap_or_default();
+        let Ok(Some(name)) = window().prompt_with_message_and_default("Rename the list to", &name) else { return };
+
+        let mut result = Ok(false);
+        set_lists.update(|lists| result = lists.rename(id, name));
+        set_error.set(result.err());
+    };
+
+    let delete = move |id: u64| {
+        let Some((name, count)) = lists.with_untracked(|lists| {
+            lists.find(id).map(|list| (list.name.clone(), list.todos.get().len()))
+        }) else { return };
+        let confirmed = window()
+            .confirm_with_message(&format!("Delete \"{name}\" and its {count} to-dos?"))
+            .unwrap_or(false);
+        if !confirmed {
+            return;
+        }
+
+        set_lists.update(|lists| { lists.delete(id); });
+        // don't stay on the page of a list that's gone
+        if location.pathname.get_untracked() == format!("/todo/{id}") {
+            navigate.with_value(|navigate| navigate("/todo", Default::default()));
+        }
+    };
+
+    view! {
+        <div>
+            <div>
+                <h3>"Lists"</h3>
+                <ul>
+                    // rows are keyed by id, so the name and count are read
+                    // from the signal to keep them up to date
+                    <For
+                        each=move || lists.with(|lists| lists.0.iter().map(|list| list.id).collect::<Vec<_>>())
+                        key=|id| *id
+                        children=move |id| {
+                            let name = move || lists.with(|lists| {
+                                lists.find(id).map(|list| list.name.clone()).unwrap_or_default()
+                            });
+                            let remaining = move || lists.with(|lists| {
+                                lists.find(id).map(|list| list.todos.num_remaining()).unwrap_or_default()
+                            });
+                            view! {
+                                <li>
+                                    <A href=id.to_string()>{name}</A>
+                                    " (" {remaining} ") "
+                                    <button on:click=move |_| rename(id)>"Rename"</button>
+                                    <button on:click=move |_| delete(id)>"Delete"</button>
+                                </li>
+                            }
+                        }
+                    >
+                    </For>
+                </ul>
+                <p>
+                    <A href="calendar">"Calendar"</A>
+                    " "
+                    <A href="stats">"Stats"</A>
+                </p>
+                <SyncStatus/>
+                <form on:submit=on_new_list>
+                    <input type="text" placeholder="list name" node_ref=name_element/>
+                    <input type="submit" value="New list"/>
+                </form>
+                {move || error.get().map(|err| view! { <p class="error">{err.to_string()}</p> })}
+            </div>
+
+            // <Outlet/> is where the nested route renders the list that's
+            // picked, or a message if none is.
+            <Outlet/>
+        </div>
+    }
+}
+
+/// The list whose id is in the url, or a message if there's no such list.
+#[component]
+pub fn SelectedList() -> impl IntoView {
+
+    let ListsState { lists, .. } = expect_context();
+
+    // `use_params` parses the `:list_id` part of the url into `ListParams`.
+    // Ids that aren't numbers and ids of deleted lists both end up as `None`.
+    // The memo only changes when a different list is picked, so the list
+    // isn't rebuilt every time one of its todos changes.
+    let params = use_params::<ListParams>();
+    let list_id = create_memo(move |_| {
+        params.with(|params| params.as_ref().ok().and_then(|params| params.list_id))
+            .filter(|id| lists.with(|lists| lists.find(*id).is_some()))
+    });
+
+    move || match list_id.get() {
+        Some(list_id) => view! { <ToDoApp list_id=list_id/> }.into_view(),
+        None => view! {
+            <p class="error">"There's no to-do list here. Pick one of the lists or make a new one."</p>
+        }.into_view(),
+    }
+}
+
+/// A signal of its own for the todos of one list. It starts with the todos
+/// as they were last saved and is kept in step with the lists both ways:
+/// changes made to it are copied into the lists (and from there saved and
+/// synced), and changes made to the lists elsewhere, like todos pulled from
+/// the sync server, are copied into it.
+fn list_todos(
+    list_id: u64,
+    lists: ReadSignal<Lists>,
+    set_lists: WriteSignal<Lists>,
+) -> (ReadSignal<ToDos>, WriteSignal<ToDos>) {
+    let saved = lists.with_untracked(|lists| lists.find(list_id).map(|list| list.todos.clone()));
+    let (todos, set_todos) = create_signal(saved.unwrap_or(ToDos(Vec::new())));
+
+    // an effect re-runs every time a signal it reads changes. Each one only
+    // writes when the two copies differ, so they don't set each other off
+    // forever.
+    create_effect(move |_| {
+        let todos = todos.get();
+        let changed = lists.with_untracked(|lists| lists.find(list_id).is_some_and(|list| list.todos != todos));
+        if changed {
+            set_lists.update(|lists| if let Some(list) = lists.find_mut(list_id) {
+                list.todos = todos;
+            });
+        }
+    });
+    create_effect(move |_| {
+        let Some(saved) = lists.with(|lists| lists.find(list_id).map(|list| list.todos.clone())) else { return };
+        if todos.with_untracked(|todos| *todos != saved) {
+            set_todos.set(saved);
+        }
+    });
+
+    (todos, set_todos)
+}
 
 /// When unit testing a component, the best thing to do is to extract the
 /// logic from the macro and place it in it's own Rust type. This type can
 /// then be tested normally.
 #[component]
-pub fn ToDoApp() -> impl IntoView {
-    
+fn ToDoApp(list_id: u64) -> impl IntoView {
+
+    let ListsState { lists, set_lists } = expect_context();
+
     // create a signal to listen to a Struct for changes
-    let (todos, set_todos) = create_signal(ToDos(Vec::new()));
+    let (todos, set_todos) = list_todos(list_id, lists, set_lists);
+
+    // every change goes through the history so it can be undone. Only the
+    // todos are saved, the history starts fresh on each visit.
+    let (history, set_history) = create_signal(History::new(HISTORY_LIMIT));
+    // the pomodoro timer isn't saved either, only the focus time it adds to
+    // the todos
+    let (pomodoro, set_pomodoro) = create_signal(None::<Pomodoro>);
+    let (selection, set_selection) = create_signal(Selection::default());
+    // the row highlighted by the keyboard shortcuts, and the row whose
+    // title is being edited
+    let (cursor, set_cursor) = create_signal(None::<u64>);
+    let (editing, set_editing) = create_signal(None::<u64>);
+    // the rows whose sub-tasks are hidden
+    let (collapsed, set_collapsed) = create_signal(BTreeSet::<u64>::new());
+    let state = ToDoState {
+        todos, set_todos,
+        history, set_history,
+        pomodoro, set_pomodoro,
+        selection, set_selection,
+        cursor, set_cursor,
+        editing, set_editing,
+        collapsed, set_collapsed,
+    };
+
+    // the filter lives in the url (`/todo/0?filter=active`) so a filtered
+    // view can be bookmarked and the back button moves between filters.
+    // `use_query` parses the query string into the `ToDoQuery` struct.
+    let query = use_query::<ToDoQuery>();
+    let filter = move || query.with(|query| {
+        query.as_ref().ok().and_then(|query| query.filter).unwrap_or_default()
+    });
+
+    // so does the choice between the list and the board (`?view=board`).
+    // It's a memo so changing the filter doesn't rebuild the whole view.
+    let view_mode = create_memo(move |_| query.with(|query| {
+        query.as_ref().ok().and_then(|query| query.view).unwrap_or_default()
+    }));
+
+    // how the list is ordered
+    let (sort, set_sort) = create_signal(SortOrder::default());
+
+    // only todos with every one of these tags are listed
+    let (selected_tags, set_selected_tags) = create_signal(Vec::<String>::new());
+
+    // a memo only notifies the rows when the options actually change
+    let options = create_memo(move |_| ListOptions {
+        filter: filter(),
+        tags: selected_tags.get(),
+        sort: sort.get(),
+    });
+
+    // rows that are deleted or filtered out drop out of the selection, so a
+    // batch action only ever changes rows that can be seen
+    let shown = move || options.with(|options| todos.with(|todos| todos.shown(options)));
+    // the cursor only moves between the rows that can be seen
+    let visible = move || options.with_untracked(|options| todos.with_untracked(|todos| {
+        collapsed.with_untracked(|collapsed| todos.visible(options, collapsed))
+    }));
+    create_effect(move |_| {
+        let shown = shown();
+        set_selection.update(|selection| selection.retain(|id| shown.contains(&id)));
+    });
+
+    // todos due before today are highlighted
+    let today = Date::today();
+
+    // listen to an input box for changes
+    let input_element: NodeRef<html::Input> = create_node_ref();
+    let due_element: NodeRef<html::Input> = create_node_ref();
+    let priority_element: NodeRef<html::Select> = create_node_ref();
+    let interval_element: NodeRef<html::Input> = create_node_ref();
+
+    // which kind of recurrence is picked decides which extra inputs are shown
+    let (repeat, set_repeat) = create_signal(String::new());
+    let (weekdays, set_weekdays) = create_signal(Vec::<Weekday>::new());
+
+    // the reason the last new title was rejected, if it was
+    let (add_error, set_add_error) = create_signal(None::<TitleError>);
+
+    // whether the cheat sheet of keyboard shortcuts is open
+    let (help, set_help) = create_signal(false);
+
+    // in the manual order the highlighted row swaps places with the row
+    // above or below it
+    let reorder = move |id: u64, place: Place| {
+        let neighbour = options.with_untracked(|options| {
+            let manual = options.sort == SortOrder::Manual;
+            todos.with_untracked(|todos| todos.neighbour(id, options, place).filter(|_| manual))
+        });
+        if let Some(neighbour) = neighbour {
+            _ = state.dispatch(ToDoAction::Reorder(id, neighbour, place));
+            // the row is taken out and put back in, which loses focus.
+            // Telling the rows the cursor changed has it focused again.
+            set_cursor.update(|_| {});
+        }
+    };
+
+    let run = move |command: Command| {
+        let cursor = cursor.get_untracked();
+        match command {
+            Command::Down => set_cursor.set(shortcuts::step(&visible(), cursor, 1)),
+            Command::Up => set_cursor.set(shortcuts::step(&visible(), cursor, -1)),
+            Command::Toggle => if let Some(id) = cursor {
+                _ = state.dispatch(ToDoAction::Toggle(id, today));
+            }
+            Command::Edit => if cursor.is_some() {
+                set_editing.set(cursor);
+            }
+            Command::Delete => if let Some(id) = cursor {
+                let before = visible();
+                _ = state.dispatch(ToDoAction::Delete(id));
+                set_cursor.set(shortcuts::nearest(&before, id, &visible()));
+            }
+            Command::MoveUp => if let Some(id) = cursor {
+                reorder(id, Place::Before);
+            }
+            Command::MoveDown => if let Some(id) = cursor {
+                reorder(id, Place::After);
+            }
+            Command::FocusInput => if let Some(input) = input_element.get_untracked() {
+                _ = input.focus();
+            }
+            Command::Help => set_help.update(|help| *help = !*help),
+            Command::Undo => state.undo(),
+            Command::Redo => state.redo(),
+        }
+    };
+
+    // every shortcut is listed in `SHORTCUTS`. They're all ignored while the
+    // user is typing in an input, where the keys already mean something.
+    let handle = window_event_listener(ev::keydown, move |ev| {
+        let key = shortcuts::key_name(&ev.key(), ev.ctrl_key() || ev.meta_key(), ev.alt_key(), ev.shift_key());
+        let Some(command) = shortcuts::command(&key) else { return };
+        if is_typing(&ev) || (command.needs_cursor() && view_mode.get_untracked() != ViewMode::List) {
+            return;
+        }
+        ev.prevent_default();
+        run(command);
+    });
+    on_cleanup(move || handle.remove());
+
+    let on_submit = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+
+        let input = input_element.get().expect("<input> should be mounted");
+        let due_input = due_element.get().expect("<input> should be mounted");
+        let priority = priority_element.get().expect("<select> should be mounted").value();
+
+        let interval = interval_element.get()
+            .and_then(|input| input.value().parse().ok())
+            .unwrap_or(1);
+        let recurrence = match repeat.get_untracked().as_str() {
+            "daily" => Some(Recurrence::Daily),
+            "weekly" => Some(Recurrence::Weekly(weekdays.with_untracked(|weekdays| {
+                Weekday::ALL.into_iter().filter(|day| weekdays.contains(day)).collect()
+            }))),
+            "monthly" => Some(Recurrence::Monthly(interval)),
+            "every-n-days" => Some(Recurrence::EveryNDays(interval)),
+            _ => None,
+        };
+
+        // an empty date input means there's no due date, unless the todo
+        // repeats. Then it's due on the first day it falls on.
+        let due = due_input.value().parse().ok()
+            .or_else(|| recurrence.as_ref().map(|recurrence| recurrence.first_from(today)));
+
+        let todo = ToDo {
+            id: sync::new_id(),
+            due,
+            priority: priority.parse().unwrap_or_default(),
+            recurrence,
+            created_on: Some(today),
+            ..ToDo::new(input.value())
+        };
+
+        match state.dispatch(ToDoAction::Add(todo)) {
+            Ok(()) => {
+                input.set_value("");
+                due_input.set_value("");
+                set_add_error.set(None);
+            }
+            Err(err) => set_add_error.set(Some(err)),
+        }
+    };
+
+    view! {
+        <FocusTimer state=state/>
+
+        <Show when=move || help.get()>
+            <div class="overlay" on:click=move |_| set_help.set(false)>
+                <table>
+                    {
+                        SHORTCUTS.into_iter()
+                            .map(|(key, _, description)| view! {
+                                <tr>
+                                    <td><kbd>{key}</kbd></td>
+                                    <td>{description}</td>
+                                </tr>
+                            })
+                            .collect_view()
+                    }
+                </table>
+            </div>
+        </Show>
+
+        <form on:submit=on_submit>
+            <input type="text"
+                placeholder="task"
+                node_ref=input_element
+            />
+            <input type="date" node_ref=due_element/>
+            <select node_ref=priority_element>
+                {
+                    Priority::ALL.into_iter()
+                        .map(|priority| view! {
+                            <option
+                                value=priority.to_string()
+                                selected=priority == Priority::default()
+                            >
+                                {priority.label()}
+                            </option>
+                        })
+                        .collect_view()
+                }
+            </select>
+            <select on:change=move |ev| set_repeat.set(event_target_value(&ev))>
+                <option value="">"Doesn't repeat"</option>
+                <option value="daily">"Daily"</option>
+                <option value="weekly">"Weekly"</option>
+                <option value="monthly">"Monthly"</option>
+                <option value="every-n-days">"Every N days"</option>
+            </select>
+            <Show when=move || repeat.with(|repeat| repeat == "weekly")>
+                {
+                    Weekday::ALL.into_iter()
+                        .map(|day| view! {
+                            <label>
+                                <input type="checkbox"
+                                    prop:checked=move || weekdays.with(|weekdays| weekdays.contains(&day))
+                                    on:change=move |_| set_weekdays.update(|weekdays| {
+                                        match weekdays.iter().position(|selected| *selected == day) {
+                                            Some(index) => { weekdays.remove(index); }
+                                            None => weekdays.push(day),
+                                        }
+                                    })
+                                />
+                                {day.label()}
+                            </label>
+                        })
+                        .collect_view()
+                }
+            </Show>
+            <Show when=move || repeat.with(|repeat| repeat == "monthly" || repeat == "every-n-days")>
+                <label>
+                    {move || if repeat.with(|repeat| repeat == "monthly") { " on day " } else { " every " }}
+                    <input type="number" min="1" value="1" node_ref=interval_element/>
+                </label>
+            </Show>
+            <input type="submit" value="Submit"/>
+            {move || add_error.get().map(|err| view! { <span class="error">" " {err.to_string()}</span> })}
+        </form>
+
+        // `todos.with` is a lot like `todos.get` in that it listens for a change
+        // in the tods signal. Instead of returning a primitive value though, it returns
+        // the return value of some custom logic.
+        <p>"To Dos remaining: " {move || todos.with(|todos| todos.num_remaining())}</p>
+        <p>"Overdue: " {move || todos.with(|todos| todos.num_overdue(today))}</p>
+
+        <button
+            on:click=move |_| { _ = state.dispatch(ToDoAction::ClearCompleted); }
+        >
+            "Clear completed"
+        </button>
+        <button
+            disabled=move || !state.can_undo()
+            on:click=move |_| state.undo()
+        >
+            "Undo"
+        </button>
+        <button
+            disabled=move || !state.can_redo()
+            on:click=move |_| state.redo()
+        >
+            "Redo"
+        </button>
+        <button on:click=move |_| set_help.set(true)>"Shortcuts (?)"</button>
+
+        // the router picks up clicks on plain links, so these only change
+        // the query string rather than reloading the page.
+        <p>
+            {
+                Filter::ALL.into_iter()
+                    .map(|option| view! {
+                        <a
+                            href=move || query_href(option, view_mode.get())
+                            class:selected=move || filter() == option
+                        >
+                            {option.label()}
+                        </a>
+                        " "
+                    })
+                    .collect_view()
+            }
+            " | "
+            {
+                ViewMode::ALL.into_iter()
+                    .map(|mode| view! {
+                        <a
+                            href=move || query_href(filter(), mode)
+                            class:selected=move || view_mode.get() == mode
+                        >
+                            {mode.label()}
+                        </a>
+                        " "
+                    })
+                    .collect_view()
+            }
+        </p>
+
+        // one button per tag. Clicking a tag adds it to, or removes it from,
+        // the tags the list is filtered by.
+        <p>
+            {move || {
+                todos.with(ToDos::tag_counts)
+                    .into_iter()
+                    .map(|(tag, count)| {
+                        let label = format!("#{tag} ({count})");
+                        // `store_value` makes the tag `Copy` so both closures can use it
+                        let tag = store_value(tag);
+                        view! {
+                            <button
+                                class:selected=move || selected_tags.with(|tags| tag.with_value(|tag| tags.contains(tag)))
+                                on:click=move |_| set_selected_tags.update(|tags| tag.with_value(|tag| {
+                                    match tags.iter().position(|selected| selected == tag) {
+                                        Some(index) => { tags.remove(index); }
+                                        None => tags.push(tag.clone()),
+                                    }
+                                }))
+                            >
+                                {label}
+                            </button>
+                            " "
+                        }
+                    })
+                    .collect_view()
+            }}
+            <Show when=move || selected_tags.with(|tags| !tags.is_empty())>
+                <button on:click=move |_| set_selected_tags.set(Vec::new())>"Any tag"</button>
+            </Show>
+        </p>
+
+        <label>
+            "Sort by "
+            <select on:change=move |ev| set_sort.set(event_target_value(&ev).parse().unwrap_or_default())>
+                {
+                    SortOrder::ALL.into_iter()
+                        .map(|option| view! {
+                            <option value=option.to_string() selected=move || sort.get() == option>
+                                {option.label()}
+                            </option>
+                        })
+                        .collect_view()
+                }
+            </select>
+        </label>
+
+        {move || match view_mode.get() {
+            // list the top level todos. `For` is keyed by the id of each todo
+            // so only rows that were added or removed get rebuilt. Sub-tasks
+            // are listed by the `ToDoItem` of their parent.
+            ViewMode::List => view! {
+                <label>
+                    <input type="checkbox"
+                        prop:checked=move || selection.with(|selection| selection.contains_all(&shown()))
+                        on:change=move |_| set_selection.update(|selection| selection.toggle_all(&shown()))
+                    />
+                    " Select all"
+                </label>
+                <Show when=move || selection.with(|selection| !selection.is_empty())>
+                    <BatchActions state=state list_id=list_id today=today/>
+                </Show>
+                <ul>
+                    <For
+                        each=move || options.with(|options| todos.with(|todos| todos.sorted(None, options)))
+                        key=|todo| todo.id
+                        children=move |todo| view! { <ToDoItem state=state id=todo.id options=options today=today/> }
+                    >
+                    </For>
+                </ul>
+            }.into_view(),
+            ViewMode::Board => view! { <Board state=state options=options today=today/> }.into_view(),
+        }}
+
+        <ImportExport state=state list_id=list_id/>
+    }
+
+}
+
+/// A todo and, underneath it, its sub-tasks. Sub-tasks are rendered with
+/// this same component so they can have sub-tasks of their own.
+#[component]
+fn ToDoItem(
+    state: ToDoState,
+    id: u64,
+    /// How the sub-tasks are filtered and ordered.
+    options: Memo<ListOptions>,
+    today: Date,
+) -> impl IntoView {
+
+    let todos = state.todos;
+
+    // a row is only created once per id, so read the todo from the signal
+    // to keep the row up to date.
+    let completed = move || todos.with(|todos| {
+        todos.find(id).is_some_and(ToDo::is_completed)
+    });
+    let overdue = move || todos.with(|todos| {
+        todos.find(id).is_some_and(|todo| todo.is_overdue(today))
+    });
+    let title = move || todos.with(|todos| {
+        todos.find(id).map(|todo| todo.title.clone()).unwrap_or_default()
+    });
+    let details = move || todos.with(|todos| {
+        todos.find(id).map(ToDo::details).unwrap_or_default()
+    });
+
+    let selected = move || state.selection.with(|selection| selection.contains(id));
+    let highlighted = move || state.cursor.with(|cursor| *cursor == Some(id));
+
+    // shift-clicking the box selects every row from the last one clicked
+    let on_select = move |ev: ev::MouseEvent| {
+        let shown = options.with_untracked(|options| todos.with_untracked(|todos| todos.shown(options)));
+        state.set_selection.update(|selection| selection.click(id, ev.shift_key(), &shown));
+    };
+
+    // whether the pomodoro timer is running on this todo
+    let focused = move || state.pomodoro.with(|pomodoro| pomodoro.as_ref().is_some_and(|pomodoro| pomodoro.todo() == id));
+
+    // how many of the sub-tasks (and their sub-tasks) are completed
+    let progress = move || todos.with(|todos| todos.progress(id));
+    let has_sub_tasks = move || progress().is_some();
+    let percent_done = move || progress().map_or(0, |(done, total)| (done * 100 / total) as i32);
+
+    // the reason the last change to this todo was rejected, if it was
+    let (error, set_error) = create_signal(None::<TitleError>);
+
+    // in the manual order rows can be dragged onto each other or moved with
+    // Alt+Up and Alt+Down
+    let manual = move || options.with(|options| options.sort == SortOrder::Manual);
+    let row: NodeRef<html::Li> = create_node_ref();
+
+    // the row the shortcuts highlight is focused so it scrolls into view,
+    // and focusing a row, e.g. by clicking it, highlights it
+    create_effect(move |_| if highlighted() {
+        if let Some(row) = row.get_untracked() {
+            _ = row.focus();
+        }
+    });
+
+    let on_drag_start = move |ev: ev::DragEvent| {
+        // rows are nested, so stop the rows around this one from taking over
+        // the drag
+        ev.stop_propagation();
+        if let Some(data) = ev.data_transfer() {
+            _ = data.set_data("text/plain", &id.to_string());
+        }
+    };
+
+    let on_drop = move |ev: ev::DragEvent| {
+        ev.prevent_default();
+        ev.stop_propagation();
+        let dragged = ev.data_transfer()
+            .and_then(|data| data.get_data("text/plain").ok())
+            .and_then(|data| data.parse().ok());
+        let (Some(dragged), Some(row)) = (dragged, row.get_untracked()) else { return };
+
+        // dropping on the top half of a row puts the todo before it
+        let rect = row.get_bounding_client_rect();
+        let place = if (ev.client_y() as f64) < rect.top() + rect.height() / 2.0 {
+            Place::Before
+        } else {
+            Place::After
+        };
+        _ = state.dispatch(ToDoAction::Reorder(dragged, id, place));
+    };
+
+    // completing a todo that still has open sub-tasks offers to complete
+    // them too
+    let on_toggle = move |ev: ev::Event| {
+        let open_sub_tasks = todos.with_untracked(|todos| {
+            let completing = todos.find(id).is_some_and(|todo| !todo.is_completed());
+            if completing { todos.num_open_sub_tasks(id) } else { 0 }
+        });
+        let complete_all = open_sub_tasks > 0
+            && window()
+                .confirm_with_message(&format!("Also complete its {open_sub_tasks} open sub-tasks?"))
+                .unwrap_or(false);
+
+        _ = state.dispatch(if complete_all { ToDoAction::Complete(id, today) } else { ToDoAction::Toggle(id, today) });
+
+        // a recurring todo stays open when it's ticked, so `completed`
+        // doesn't change and the box has to be unticked by hand
+        event_target::<web_sys::HtmlInputElement>(&ev).set_checked(completed());
+    };
+
+    // double clicking the title, or pressing E on the highlighted row, swaps
+    // it for an input. Enter or leaving the input saves the new title, Escape
+    // throws it away. Only one row is edited at a time.
+    let editing = move || state.editing.with(|editing| *editing == Some(id));
+
+    let stop_editing = move || {
+        state.set_editing.update(|editing| if *editing == Some(id) {
+            *editing = None;
+        });
+        set_error.set(None);
+    };
+    let commit = move |value: String| {
+        // removing the input fires a blur, so ignore it if the
+        // edit was already saved or cancelled.
+        if !state.editing.with_untracked(|editing| *editing == Some(id)) {
+            return;
+        }
+        match state.dispatch(ToDoAction::Edit(id, value)) {
+            Ok(()) => stop_editing(),
+            Err(err) => set_error.set(Some(err)),
+        }
+    };
+
+    // whether the sub-tasks are shown. Collapsing the row while the cursor
+    // is on one of them moves it up to this row, so it stays on a row that
+    // can be seen.
+    let expanded = move || state.collapsed.with(|collapsed| !collapsed.contains(&id));
+    let set_expanded = move |expanded: bool| state.set_collapsed.update(|collapsed| {
+        if expanded {
+            collapsed.remove(&id);
+        } else {
+            collapsed.insert(id);
+        }
+    });
+    let on_collapse = move |_: ev::MouseEvent| {
+        let collapsing = state.collapsed.with_untracked(|collapsed| !collapsed.contains(&id));
+        set_expanded(!collapsing);
+        let hidden = state.cursor.get_untracked()
+            .is_some_and(|cursor| todos.with_untracked(|todos| todos.descendants(id).contains(&cursor)));
+        if collapsing && hidden {
+            state.set_cursor.set(Some(id));
+        }
+    };
+
+    // "+ Sub-task" opens a small form underneath the todo
+    let (adding, set_adding) = create_signal(false);
+    let sub_task_element: NodeRef<html::Input> = create_node_ref();
+
+    let on_add_sub_task = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+
+        let input = sub_task_element.get().expect("<input> should be mounted");
+        let sub_task = ToDo {
+            id: sync::new_id(),
+            parent: Some(id),
+            created_on: Some(today),
+            ..ToDo::new(input.value())
+        };
+
+        match state.dispatch(ToDoAction::Add(sub_task)) {
+            Ok(()) => {
+                set_adding.set(false);
+                set_expanded(true);
+                set_error.set(None);
+            }
+            Err(err) => set_error.set(Some(err)),
+        }
+    };
+
+    view! {
+        <li
+            class:completed=completed
+            class:overdue=overdue
+            class:picked=selected
+            class:cursor=highlighted
+            tabindex="0"
+            draggable=move || manual().to_string()
+            node_ref=row
+            on:dragstart=on_drag_start
+            on:dragover=move |ev: ev::DragEvent| if manual() { ev.prevent_default() }
+            on:drop=on_drop
+            on:focus=move |_| state.set_cursor.set(Some(id))
+        >
+            <Show when=has_sub_tasks>
+                <button on:click=on_collapse>
+                    {move || if expanded() { "▾" } else { "▸" }}
+                </button>
+            </Show>
+            <input type="checkbox"
+                title="Select"
+                prop:checked=selected
+                on:click=on_select
+            />
+            <input type="checkbox"
+                title="Done"
+                prop:checked=completed
+                on:change=on_toggle
+            />
+            {move || if editing() {
+                let edit_input: NodeRef<html::Input> = create_node_ref();
+                edit_input.on_load(|input| { _ = input.focus(); });
+
+                view! {
+                    <input type="text"
+                        value=title
+                        node_ref=edit_input
+                        on:keydown=move |ev| match ev.key().as_str() {
+                            "Enter" => commit(event_target_value(&ev)),
+                            "Escape" => stop_editing(),
+                            _ => {}
+                        }
+                        on:blur=move |ev| commit(event_target_value(&ev))
+                    />
+                }.into_view()
+            } else {
+                view! {
+                    <span on:dblclick=move |_| state.set_editing.set(Some(id))>{title}</span>
+                }.into_view()
+            }}
+            <small>{details}</small>
+            <Show when=has_sub_tasks>
+                " "
+                <ProgressBar progress=Signal::derive(percent_done)/>
+            </Show>
+            <button
+                disabled=focused
+                on:click=move |_| state.start_focus(id)
+            >
+                {move || if focused() { "Focusing…" } else { "Focus" }}
+            </button>
+            <button on:click=move |_| set_adding.set(true)>"+ Sub-task"</button>
+            <button
+                on:click=move |_| { _ = state.dispatch(ToDoAction::Delete(id)); }
+            >
+                "Delete"
+            </button>
+            {move || error.get().map(|err| view! { <span class="error">" " {err.to_string()}</span> })}
+
+            <Show when=move || adding.get()>
+                <form on:submit=on_add_sub_task>
+                    <input type="text" placeholder="sub-task" node_ref=sub_task_element/>
+                    <input type="submit" value="Add"/>
+                    <button type="button" on:click=move |_| set_adding.set(false)>"Cancel"</button>
+                </form>
+            </Show>
+
+            <Show when=move || expanded() && has_sub_tasks()>
+                <ul>
+                    <For
+                        each=move || options.with(|options| todos.with(|todos| todos.sorted(Some(id), options)))
+                        key=|todo| todo.id
+                        children=move |todo| {
+                            view! { <ToDoItem state=state id=todo.id options=options today=today/> }.into_view()
+                        }
+                    >
+                    </For>
+                </ul>
+            </Show>
+        </li>
+    }
+}
+
+/// The pomodoro timer, while one is running: the todo being worked on, the
+/// time left in the current phase and buttons to pause, skip or stop it.
+#[component]
+fn FocusTimer(state: ToDoState) -> impl IntoView {
+
+    let pomodoro = state.pomodoro;
+
+    // count down once a second. Dropping the interval cancels it, so it
+    // stops when the list is closed. A pomodoro left running then loses
+    // the focus time of its unfinished work interval.
+    let interval = Interval::new(1000, move || state.update_pomodoro(Pomodoro::tick));
+    on_cleanup(move || drop(interval));
+
+    let title = move || {
+        let id = pomodoro.with(|pomodoro| pomodoro.as_ref().map(Pomodoro::todo));
+        state.todos.with(|todos| id.and_then(|id| todos.find(id)).map(|todo| todo.title.clone()))
+    };
+    let phase = move || pomodoro.with(|pomodoro| pomodoro.as_ref().map(|p| p.phase().label()));
+    let remaining = move || pomodoro.with(|pomodoro| pomodoro.as_ref().map(|p| pomodoro::clock(p.remaining())));
+    let percent_done = move || pomodoro.with(|pomodoro| pomodoro.as_ref().map_or(0, Pomodoro::percent_done));
+    let paused = move || pomodoro.with(|pomodoro| pomodoro.as_ref().is_some_and(Pomodoro::is_paused));
+
+    view! {
+        <Show when=move || pomodoro.with(Option::is_some)>
+            <p>
+                {phase} " on \"" {title} "\": " {remaining} " "
+                <ProgressBar progress=Signal::derive(percent_done)/>
+                " "
+                <button on:click=move |_| state.update_pomodoro(Pomodoro::toggle_pause)>
+                    {move || if paused() { "Resume" } else { "Pause" }}
+                </button>
+                <button on:click=move |_| state.update_pomodoro(Pomodoro::skip)>"Skip"</button>
+                <button on:click=move |_| state.stop_focus()>"Stop"</button>
+            </p>
+        </Show>
+    }
+}
+
+/// The same todos as the list, shown as cards in a column per status.
+/// Cards are moved between columns by dragging them or with their arrow
+/// buttons.
+#[component]
+fn Board(state: ToDoState, options: Memo<ListOptions>, today: Date) -> impl IntoView {
+
+    let todos = state.todos;
+
+    let columns = Status::ALL.into_iter()
+        .map(|status| {
+            let cards = move || options.with(|options| todos.with(|todos| todos.column(status, options)));
+
+            let on_drop = move |ev: ev::DragEvent| {
+                ev.prevent_default();
+                let dragged = ev.data_transfer()
+                    .and_then(|data| data.get_data("text/plain").ok())
+                    .and_then(|data| data.parse().ok());
+                if let Some(id) = dragged {
+                    _ = state.dispatch(ToDoAction::SetStatus(id, status, today));
+                }
+            };
+
+            view! {
+                <section
+                    on:dragover=|ev: ev::DragEvent| ev.prevent_default()
+                    on:drop=on_drop
+                >
+                    <h3>{status.label()} " (" {move || cards().len()} ")"</h3>
+                    <ul>
+                        <For
+                            each=cards
+                            key=|todo| todo.id
+                            children=move |todo| view! { <Card state=state id=todo.id today=today/> }
+                        >
+                        </For>
+                    </ul>
+                </section>
+            }
+        })
+        .collect_view();
+
+    view! { <div class="board">{columns}</div> }
+}
+
+/// One todo on the board.
+#[component]
+fn Card(state: ToDoState, id: u64, today: Date) -> impl IntoView {
+
+    let todos = state.todos;
+
+    let status = move || todos.with(|todos| todos.find(id).map(|todo| todo.status).unwrap_or_default());
+    let overdue = move || todos.with(|todos| {
+        todos.find(id).is_some_and(|todo| todo.is_overdue(today))
+    });
+    let title = move || todos.with(|todos| {
+        todos.find(id).map(|todo| todo.title.clone()).unwrap_or_default()
+    });
+    let details = move || todos.with(|todos| {
+        todos.find(id).map(ToDo::details).unwrap_or_default()
+    });
+    let progress = move || todos.with(|todos| todos.progress(id));
+    let percent_done = move || progress().map_or(0, |(done, total)| (done * 100 / total) as i32);
+
+    let move_to = move |status: Option<Status>| {
+        if let Some(status) = status {
+            _ = state.dispatch(ToDoAction::SetStatus(id, status, today));
+        }
+    };
+
+    view! {
+        <li
+            class:overdue=overdue
+            draggable="true"
+            on:dragstart=move |ev: ev::DragEvent| {
+                if let Some(data) = ev.data_transfer() {
+                    _ = data.set_data("text/plain", &id.to_string());
+                }
+            }
+        >
+            <button
+                disabled=move || status().previous().is_none()
+                on:click=move |_| move_to(status().previous())
+            >
+                "←"
+            </button>
+            " " {title} " "
+            <button
+                disabled=move || status().next().is_none()
+                on:click=move |_| move_to(status().next())
+            >
+                "→"
+            </button>
+            <br/>
+            <small>{details}</small>
+            <Show when=move || progress().is_some()>
+                <br/>
+                <ProgressBar progress=Signal::derive(percent_done)/>
+            </Show>
+        </li>
+    }
+}
+
+/// What can be done to every selected row at once. Each change is one
+/// `ToDoAction::Batch`, so it goes through the history like any other edit
+/// and is undone in one step.
+#[component]
+fn BatchActions(state: ToDoState, list_id: u64, today: Date) -> impl IntoView {
+
+    let ListsState { lists, set_lists } = expect_context();
+    let selection = state.selection;
+
+    let priority_element: NodeRef<html::Select> = create_node_ref();
+    let tags_element: NodeRef<html::Input> = create_node_ref();
+    let list_element: NodeRef<html::Select> = create_node_ref();
+
+    // the reason the last batch was rejected, if it was
+    let (error, set_error) = create_signal(None::<TitleError>);
+
+    let batch = move |action: &dyn Fn(u64) -> ToDoAction| {
+        let actions = selection.with_untracked(Selection::ids).into_iter().map(action).collect();
+        set_error.set(state.dispatch(ToDoAction::Batch(actions)).err());
+    };
+
+    let on_priority = move |_: ev::Event| {
+        let select = priority_element.get().expect("<select> should be mounted");
+        if let Ok(priority) = select.value().parse() {
+            batch(&|id| ToDoAction::SetPriority(id, priority));
+        }
+        select.set_value("");
+    };
+
+    // the tags are typed the same way as in a title, e.g. "#work #urgent".
+    // An empty box removes every tag.
+    let on_retag = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+        let input = tags_element.get().expect("<input> should be mounted");
+        let (_, tags) = ToDos::extract_tags(&input.value());
+        batch(&|id| ToDoAction::SetTags(id, tags.clone()));
+        input.set_value("");
+    };
+
+    // moving adds the todos, with their sub-tasks, to the other list and
+    // deletes them from this one. Undo could only put them back here while
+    // the other list kept its copies, so a move isn't recorded in the
+    // history and can't be undone. Neither can anything before it: undoing
+    // the import that added the todos, say, would bring them back here too.
+    let on_move = move |_: ev::Event| {
+        let select = list_element.get().expect("<select> should be mounted");
+        let target = select.value().parse::<u64>().ok();
+        select.set_value("");
+        let Some(target) = target else { return };
+
+        let moving = state.todos.with_untracked(|todos| todos.with_sub_tasks(&selection.with_untracked(Selection::ids)));
+        // try it on a copy first so nothing moves if a title is already
+        // taken in the other list
+        let taken = lists.with_untracked(|lists| {
+            lists.find(target).map(|list| list.todos.clone().import(moving.clone(), false))
+        });
+        match taken.map(|taken| taken.into_iter().next()) {
+            Some(None) => {}
+            Some(Some(err)) => {
+                set_error.set(Some(err));
+                return;
+            }
+            None => return,
+        }
+
+        set_lists.update(|lists| if let Some(list) = lists.find_mut(target) {
+            _ = list.todos.reduce(&ToDoAction::Import(moving, false));
+        });
+        let deletes = selection.with_untracked(Selection::ids).into_iter().map(ToDoAction::Delete).collect();
+        let result = state.apply(ToDoAction::Batch(deletes));
+        if result.is_ok() {
+            state.set_history.update(History::clear);
+        }
+        set_error.set(result.err());
+    };
+
+    view! {
+        <p>
+            {move || selection.with(Selection::len)} " selected: "
+            <button on:click=move |_| batch(&|id| ToDoAction::Complete(id, today))>"Complete"</button>
+            <button on:click=move |_| batch(&ToDoAction::Delete)>"Delete"</button>
+            " "
+            <select node_ref=priority_element on:change=on_priority>
+                <option value="">"Set priority…"</option>
+                {
+                    Priority::ALL.into_iter()
+                        .map(|priority| view! { <option value=priority.to_string()>{priority.label()}</option> })
+                        .collect_view()
+                }
+            </select>
+            " "
+            <select node_ref=list_element on:change=on_move>
+                <option value="">"Move to…"</option>
+                {move || lists.with(|lists| {
+                    lists.0.iter()
+                        .filter(|list| list.id != list_id)
+                        .map(|list| view! { <option value=list.id.to_string()>{list.name.clone()}</option> })
+                        .collect_view()
+                })}
+            </select>
+            " "
+            <button on:click=move |_| state.set_selection.update(Selection::clear)>"Clear selection"</button>
+        </p>
+        <form on:submit=on_retag>
+            <input type="text" placeholder="#tag #another" node_ref=tags_element/>
+            <input type="submit" value="Retag"/>
+            {move || error.get().map(|err| view! { <span class="error">" " {err.to_string()}</span> })}
+        </form>
+    }
+}
+
+/// Downloads the list as todo.txt or JSON, and reads either format back in.
+#[component]
+fn ImportExport(state: ToDoState, list_id: u64) -> impl IntoView {
+
+    let todos = state.todos;
+    let ListsState { lists, .. } = expect_context();
+
+    // the links are rebuilt whenever the list changes so a download always
+    // has the latest todos
+    let todotxt_href = move || data_url("text/plain", &todos.with(todotxt::export));
+    let json_href = move || data_url("application/json", &lists.with(|lists| storage::encode(&lists.only(list_id))));
+
+    let file_element: NodeRef<html::Input> = create_node_ref();
+    let replace_element: NodeRef<html::Input> = create_node_ref();
+
+    // everything that went wrong in the last import
+    let (problems, set_problems) = create_signal(Vec::<String>::new());
+
+    let on_import = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+
+        let file = file_element.get()
+            .and_then(|input| input.files())
+            .and_then(|files| files.get(0));
+        let Some(file) = file else {
+            set_problems.set(vec!["Choose a file to import.".to_string()]);
+            return;
+        };
+        let replace = replace_element.get().expect("<input> should be mounted").checked();
+
+        // reading a file is asynchronous, so finish the import in a future
+        spawn_local(async move {
+            let text = JsFuture::from(file.text()).await
+                .ok()
+                .and_then(|text| text.as_string())
+                .unwrap_or_default();
+
+            let (mut drafts, mut found) = if file.name().ends_with(".json") {
+                match storage::decode(&text) {
+                    Ok(imported) => (imported.0.into_iter().flat_map(|list| list.todos.0).collect(), Vec::new()),
+                    Err(err) => (Vec::new(), vec![err.to_string()]),
+                }
+            } else {
+                let (drafts, errors) = todotxt::parse(&text);
+                (drafts, errors.iter().map(ToString::to_string).collect())
+            };
+
+            ToDos::renumber(&mut drafts, sync::new_id());
+
+            // try the import on a copy first to find out what will be skipped
+            let skipped = todos.with_untracked(|todos| todos.clone().import(drafts.clone(), replace));
+            found.extend(skipped.iter().map(|err| format!("Skipped: {err}")));
+
+            _ = state.dispatch(ToDoAction::Import(drafts, replace));
+            set_problems.set(found);
+        });
+    };
+
+    view! {
+        <p>
+            <a download="todo.txt" href=todotxt_href>"Export todo.txt"</a>
+            " "
+            <a download="todos.json" href=json_href>"Export JSON"</a>
+        </p>
+        <form on:submit=on_import>
+            <input type="file" accept=".txt,.json" node_ref=file_element/>
+            <label>
+                <input type="checkbox" node_ref=replace_element/>
+                " Replace current list"
+            </label>
+            <input type="submit" value="Import"/>
+        </form>
+        <ul class="error">
+            {move || problems.get().into_iter().map(|problem| view! { <li>{problem}</li> }).collect_view()}
+        </ul>
+    }
+}
+
+/// A link target that downloads `text` as a file.
+fn data_url(mime: &str, text: &str) -> String {
+    format!("data:{mime};charset=utf-8,{}", String::from(js_sys::encode_uri_component(text)))
+}
+
+/// The lists that `ToDoLists` shares with the routes nested inside it.
+#[derive(Clone, Copy)]
+struct ListsState {
+    lists: ReadSignal<Lists>,
+    set_lists: WriteSignal<Lists>,
+}
+
+/// The signals shared by every part of the to-do app. They're all `Copy`,
+/// so the whole struct can be passed into child components and closures.
+#[derive(Clone, Copy)]
+struct ToDoState {
+    todos: ReadSignal<ToDos>,
+    set_todos: WriteSignal<ToDos>,
+    history: ReadSignal<History>,
+    set_history: WriteSignal<History>,
+    /// The pomodoro timer, if one has been started on a todo.
+    pomodoro: ReadSignal<Option<Pomodoro>>,
+    set_pomodoro: WriteSignal<Option<Pomodoro>>,
+    /// The rows picked for a batch action.
+    selection: ReadSignal<Selection>,
+    set_selection: WriteSignal<Selection>,
+    /// The row highlighted by the keyboard shortcuts.
+    cursor: ReadSignal<Option<u64>>,
+    set_cursor: WriteSignal<Option<u64>>,
+    /// The row whose title is being edited.
+    editing: ReadSignal<Option<u64>>,
+    set_editing: WriteSignal<Option<u64>>,
+    /// The rows whose sub-tasks are hidden.
+    collapsed: ReadSignal<BTreeSet<u64>>,
+    set_collapsed: WriteSignal<BTreeSet<u64>>,
+}
+
+impl ToDoState {
+    /// Every change to the list goes through here. The action is logged and
+    /// recorded in the history, and the effect in `ToDoApp` saves the result.
+    fn dispatch(self, action: ToDoAction) -> Result<(), TitleError> {
+        log::debug!("{action:?}");
+        let mut result = Ok(());
+        self.set_history.update(|history| self.set_todos.update(|todos| {
+            result = history.execute(todos, action);
+        }));
+        if let Err(err) = &result {
+            log::debug!("rejected: {err}");
+        }
+        result
+    }
+
+    fn undo(self) {
+        self.set_history.update(|history| self.set_todos.update(|todos| history.undo(todos)));
+    }
+
+    fn redo(self) {
+        self.set_history.update(|history| self.set_todos.update(|todos| history.redo(todos)));
+    }
+
+    /// Whether there's anything to undo. Read in a closure, this keeps a
+    /// button's `disabled` up to date.
+    fn can_undo(self) -> bool {
+        self.history.with(History::can_undo)
+    }
+
+    fn can_redo(self) -> bool {
+        self.history.with(History::can_redo)
+    }
+
+    /// Like `dispatch`, except the change is left out of the history: it
+    /// can't be undone and doesn't clear what can be redone.
+    fn apply(self, action: ToDoAction) -> Result<(), TitleError> {
+        log::debug!("{action:?} (not recorded)");
+        let mut result = Ok(());
+        self.set_todos.update(|todos| { result = todos.reduce(&action).map(|_| ()); });
+        if let Err(err) = &result {
+            log::debug!("rejected: {err}");
+        }
+        result
+    }
+
+    /// Add focus time to a todo. It's saved like any other change, but it
+    /// isn't an edit the user made, so it's left out of the history.
+    fn add_focus_time(self, id: u64, seconds: u64) {
+        _ = self.apply(ToDoAction::AddFocusTime(id, seconds));
+    }
+
+    /// Start a pomodoro on a todo. Time spent on the todo that had the
+    /// timer before is recorded first.
+    fn start_focus(self, id: u64) {
+        self.stop_focus();
+        self.set_pomodoro.set(Some(Pomodoro::new(id)));
+    }
+
+    /// Stop the pomodoro and add the focus time it hasn't recorded yet to
+    /// its todo.
+    fn stop_focus(self) {
+        let mut stopped = None;
+        self.set_pomodoro.update(|pomodoro| stopped = pomodoro.take());
+        if let Some(mut pomodoro) = stopped {
+            self.add_focus_time(pomodoro.todo(), pomodoro.take_focus_time());
+        }
+    }
+
+    /// Change the running pomodoro, e.g. count down a second or skip to the
+    /// next phase. A work interval that ends is added to its todo.
+    fn update_pomodoro(self, change: impl FnOnce(&mut Pomodoro)) {
+        if self.pomodoro.with_untracked(Option::is_none) {
+            return;
+        }
+        let mut finished = None;
+        self.set_pomodoro.update(|pomodoro| if let Some(pomodoro) = pomodoro {
+            change(pomodoro);
+            finished = pomodoro.finished_focus().map(|seconds| (pomodoro.todo(), seconds));
+        });
+        if let Some((id, seconds)) = finished {
+            self.add_focus_time(id, seconds);
+        }
+    }
+}
+
+
+// Enclose the logic of the component in these structs to keep the component
+// minimal and focussed only on rendering HTML tags. The todos are kept in the
+// order the user put them in, which is what `SortOrder::Manual` shows.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct ToDos(Vec<ToDo>);
+
+impl ToDos {
+    /// Every todo that isn't completed counts once, whether it's a top level
+    /// todo or a sub-task. A todo with two open sub-tasks counts as three.
+    fn num_remaining(&self) -> usize {
+        self.0.iter().filter(|todo| !todo.is_completed()).count()
+    }
+
+    fn get(&self) -> Vec<ToDo> {
+        self.0.clone()
+    }
+
+    fn num_overdue(&self, today: Date) -> usize {
+        self.0.iter().filter(|todo| todo.is_overdue(today)).count()
+    }
+
+    fn filtered(&self, filter: Filter) -> Vec<ToDo> {
+        self.0.iter().filter(|todo| filter.matches(todo)).cloned().collect()
+    }
+
+    /// The direct sub-tasks of `parent`, or the top level todos if `parent`
+    /// is `None`, that match the filter and have every one of the tags in
+    /// `options`. They're in the order given by `options.sort`. Todos that tie
+    /// keep the same order relative to each other.
+    fn sorted(&self, parent: Option<u64>, options: &ListOptions) -> Vec<ToDo> {
+        let mut todos = self.filtered(options.filter);
+        todos.retain(|todo| {
+            todo.parent == parent && options.tags.iter().all(|tag| todo.tags.contains(tag))
+        });
+        match options.sort {
+            SortOrder::Manual => {}
+            SortOrder::Created => todos.sort_by_key(|todo| todo.id),
+            // todos without a due date go last
+            SortOrder::DueDate => todos.sort_by_key(|todo| (todo.due.is_none(), todo.due)),
+            SortOrder::Priority => todos.sort_by_key(|todo| Reverse(todo.priority)),
+        }
+        todos
+    }
+
+    /// The top level todos in one column of the board. They're tagged and
+    /// ordered the same way as the list, but the filter is ignored since the
+    /// columns already split the todos up by status.
+    fn column(&self, status: Status, options: &ListOptions) -> Vec<ToDo> {
+        let options = ListOptions { filter: Filter::All, ..options.clone() };
+        let mut todos = self.sorted(None, &options);
+        todos.retain(|todo| todo.status == status);
+        todos
+    }
+
+    fn find(&self, id: u64) -> Option<&ToDo> {
+        self.0.iter().find(|todo| todo.id == id)
+    }
+
+    /// The ids of a todo's sub-tasks, their sub-tasks and so on.
+    fn descendants(&self, id: u64) -> Vec<u64> {
+        let mut descendants = Vec::new();
+        let mut parents = vec![id];
+        while let Some(parent) = parents.pop() {
+            for todo in self.0.iter().filter(|todo| todo.parent == Some(parent)) {
+                descendants.push(todo.id);
+                parents.push(todo.id);
+            }
+        }
+        descendants
+    }
+
+    fn num_open_sub_tasks(&self, id: u64) -> usize {
+        self.descendants(id).into_iter()
+            .filter(|id| self.find(*id).is_some_and(|todo| !todo.is_completed()))
+            .count()
+    }
+
+    /// How many of a todo's sub-tasks, including their sub-tasks, are
+    /// completed out of how many there are. `None` if it has no sub-tasks.
+    fn progress(&self, id: u64) -> Option<(usize, usize)> {
+        let total = self.descendants(id).len();
+        (total > 0).then(|| (total - self.num_open_sub_tasks(id), total))
+    }
+
+    /// How many todos have each tag, in alphabetical order of tag.
+    fn tag_counts(&self) -> Vec<(String, usize)> {
+        let mut counts = BTreeMap::new();
+        for tag in self.0.iter().flat_map(|todo| &todo.tags) {
+            *counts.entry(tag.clone()).or_insert(0) += 1;
+        }
+        counts.into_iter().collect()
+    }
+
+    /// Split the `#tags` out of a title, e.g. `"Email Bob #work"` becomes
+    /// `"Email Bob"` and `["work"]`. Tags are lowercase and each one is only
+    /// returned once.
+    fn extract_tags(title: &str) -> (String, Vec<String>) {
+        let mut words = Vec::new();
+        let mut tags = Vec::new();
+
+        for word in title.split_whitespace() {
+            let tag = word.strip_prefix('#')
+                .filter(|tag| !tag.is_empty())
+                .filter(|tag| tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_'));
+
+            match tag {
+                Some(tag) => {
+                    let tag = tag.to_lowercase();
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+                None => words.push(word),
+            }
+        }
+
+        (words.join(" "), tags)
+    }
+
+    /// Add a todo to the end of the list and return it so the caller can
+    /// fill in the rest of its details. Tags are taken out of the title with
+    /// `extract_tags` and the rest is checked with `validate_title`.
+    fn new_todo(&mut self, title: String) -> Result<&mut ToDo, TitleError> {
+        let (title, tags) = Self::extract_tags(&title);
+        let title = self.validate_title(&title, None)?;
+        let id = self.next_id();
+        self.0.push(ToDo { id, tags, ..ToDo::new(title) });
+        Ok(self.0.last_mut().expect("a todo was just pushed"))
+    }
+
+    /// Add a copy of `draft`. It keeps the draft's id unless that's 0 or
+    /// another todo has it, when it's given a new one. The title is handled
+    /// the same way as `new_todo`, and any tags in it are added to the
+    /// draft's tags.
+    fn add(&mut self, draft: ToDo) -> Result<&mut ToDo, TitleError> {
+        let keep_id = draft.id != 0 && self.find(draft.id).is_none();
+        let todo = self.new_todo(draft.title.clone())?;
+        let tags = mem::take(&mut todo.tags);
+        let id = if keep_id { draft.id } else { todo.id };
+        *todo = ToDo { id, title: mem::take(&mut todo.title), ..draft };
+        todo.add_tags(tags);
+        Ok(todo)
+    }
+
+    /// Add todos read from a file or moved from another list, or swap the
+    /// whole list for them if `replace` is set. Their ids are handled the
+    /// same way as `add`, and sub-tasks are linked to whatever id their
+    /// parent ends up with. Todos with a title `new_todo` rejects are skipped
+    /// and the reasons returned. Their sub-tasks are moved to the top level.
+    fn import(&mut self, drafts: Vec<ToDo>, replace: bool) -> Vec<TitleError> {
+        if replace {
+            self.0.clear();
+        }
+
+        let first = self.0.len();
+        let mut new_ids = HashMap::new();
+        let mut skipped = Vec::new();
+        for draft in drafts {
+            let old_id = draft.id;
+            match self.add(draft) {
+                Ok(todo) => { new_ids.insert(old_id, todo.id); }
+                Err(err) => skipped.push(err),
+            }
+        }
+
+        for todo in &mut self.0[first..] {
+            todo.parent = todo.parent.and_then(|parent| new_ids.get(&parent).copied());
+        }
+        skipped
+    }
+
+    /// Number todos read from a file with ids counting up from `first`, and
+    /// link sub-tasks to their parent's new id. Importing the same file
+    /// twice, here or on another client, then adds new todos rather than
+    /// taking over the ones imported before.
+    fn renumber(drafts: &mut [ToDo], first: u64) {
+        let new_ids: HashMap<u64, u64> = drafts.iter().zip(first..).map(|(draft, id)| (draft.id, id)).collect();
+        for (draft, id) in drafts.iter_mut().zip(first..) {
+            draft.id = id;
+            draft.parent = draft.parent.and_then(|parent| new_ids.get(&parent).copied());
+        }
+    }
+
+    /// Toggle a todo and return it as it was before. Returns `None` if
+    /// there's no todo with this id.
+    fn toggle(&mut self, id: u64, today: Date) -> Option<ToDo> {
+        let todo = self.find_mut(id)?;
+        let before = todo.clone();
+        todo.toggle(today);
+        Some(before)
+    }
+
+    /// Change the title of a todo and return the todo as it was before. Any
+    /// `#tags` in the new title are added to the todo's tags and the rest is
+    /// checked with `validate_title`. Returns `None` if there's no todo with
+    /// this id or nothing changed.
+    fn edit(&mut self, id: u64, title: String) -> Result<Option<ToDo>, TitleError> {
+        let (title, tags) = Self::extract_tags(&title);
+        let title = self.validate_title(&title, Some(id))?;
+        let Some(todo) = self.find_mut(id) else { return Ok(None) };
+
+        let before = todo.clone();
+        todo.title = title;
+        todo.add_tags(tags);
+        Ok((*todo != before).then_some(before))
+    }
+
+    /// Complete a todo and all of its sub-tasks. Recurring ones move on to
+    /// their next due date instead. Returns the todos that changed as they
+    /// were before.
+    fn complete(&mut self, id: u64, today: Date) -> Vec<ToDo> {
+        let mut ids = self.descendants(id);
+        ids.push(id);
+
+        let mut before = Vec::new();
+        for todo in self.0.iter_mut().filter(|todo| ids.contains(&todo.id) && !todo.is_completed()) {
+            before.push(todo.clone());
+            todo.toggle(today);
+        }
+        before
+    }
+
+    /// Move a todo to another column of the board and return it as it was
+    /// before. Moving it to `Done` is the same as ticking it, so a recurring
+    /// todo moves on to its next due date instead. Returns `None` if there's
+    /// no todo with this id or it's already in that column.
+    fn set_status(&mut self, id: u64, status: Status, today: Date) -> Option<ToDo> {
+        let todo = self.find_mut(id)?;
+        if todo.status == status {
+            return None;
+        }
+
+        let before = todo.clone();
+        if status == Status::Done {
+            todo.toggle(today);
+        } else {
+            todo.status = status;
+            todo.completed_on = None;
+        }
+        Some(before)
+    }
+
+    /// Add time spent on a todo with the pomodoro timer and return the todo
+    /// as it was before. Returns `None` if there's no todo with this id or
+    /// no time to add.
+    fn add_focus_time(&mut self, id: u64, seconds: u64) -> Option<ToDo> {
+        let todo = self.find_mut(id).filter(|_| seconds > 0)?;
+        let before = todo.clone();
+        todo.focus_seconds += seconds;
+        Some(before)
+    }
+
+    /// Change the priority of a todo and return it as it was before.
+    /// Returns `None` if there's no todo with this id or it already has
+    /// that priority.
+    fn set_priority(&mut self, id: u64, priority: Priority) -> Option<ToDo> {
+        let todo = self.find_mut(id).filter(|todo| todo.priority != priority)?;
+        let before = todo.clone();
+        todo.priority = priority;
+        Some(before)
+    }
+
+    /// Replace the tags of a todo and return it as it was before. Returns
+    /// `None` if there's no todo with this id or it already has these tags.
+    fn set_tags(&mut self, id: u64, tags: Vec<String>) -> Option<ToDo> {
+        let todo = self.find_mut(id).filter(|todo| todo.tags != tags)?;
+        let before = todo.clone();
+        todo.tags = tags;
+        Some(before)
+    }
+
+    /// The todo listed just before or after this one among its parent's
+    /// sub-tasks, skipping any that are filtered out. It's the one Alt+Up or
+    /// Alt+Down swaps it with.
+    fn neighbour(&self, id: u64, options: &ListOptions, place: Place) -> Option<u64> {
+        let parent = self.find(id)?.parent;
+        let siblings = self.sorted(parent, options);
+        let index = siblings.iter().position(|todo| todo.id == id)?;
+        let neighbour = match place {
+            Place::Before => index.checked_sub(1)?,
+            Place::After => index + 1,
+        };
+        siblings.get(neighbour).map(|todo| todo.id)
+    }
+
+    /// Move a todo to just before or after another todo with the same
+    /// parent. Returns the index it was moved from, or `None` if it didn't
+    /// move.
+    fn move_todo(&mut self, id: u64, target: u64, place: Place) -> Option<usize> {
+        let from = self.0.iter().position(|todo| todo.id == id)?;
+        if id == target || self.find(target)?.parent != self.0[from].parent {
+            return None;
+        }
+
+        let todo = self.0.remove(from);
+        let target = self.0.iter().position(|todo| todo.id == target).expect("target is still in the list");
+        let to = match place {
+            Place::Before => target,
+            Place::After => target + 1,
+        };
+        self.0.insert(to, todo);
+        (to != from).then_some(from)
+    }
+
+    /// Swap a todo for another version of it with the same id.
+    fn replace(&mut self, todo: ToDo) {
+        if let Some(existing) = self.find_mut(todo.id) {
+            *existing = todo;
+        }
+    }
+
+    /// Swap a todo back to an earlier version of it, e.g. to undo a change.
+    /// The focus time it has now is kept.
+    fn put_back(&mut self, todo: ToDo) {
+        if let Some(existing) = self.find_mut(todo.id) {
+            *existing = ToDo { focus_seconds: existing.focus_seconds, ..todo };
+        }
+    }
+
+    /// Titles can't be blank or the same as another todo's title (ignoring
+    /// case). Returns the title with surrounding whitespace trimmed. `id` is
+    /// the todo being renamed, if any, so it isn't a duplicate of itself.
+    fn validate_title(&self, title: &str, id: Option<u64>) -> Result<String, TitleError> {
+        let title = title.trim();
+        if title.is_empty() {
+            return Err(TitleError::Empty);
+        }
+
+        let lowercase = title.to_lowercase();
+        let duplicate = self.0.iter()
+            .any(|todo| Some(todo.id) != id && todo.title.to_lowercase() == lowercase);
+        if duplicate {
+            return Err(TitleError::Duplicate(title.to_string()));
+        }
+
+        Ok(title.to_string())
+    }
+
+    /// Remove a todo and its sub-tasks, returning them along with the
+    /// positions they were at.
+    fn delete(&mut self, id: u64) -> Vec<(usize, ToDo)> {
+        let mut ids = self.descendants(id);
+        ids.push(id);
+        self.remove_where(|todo| ids.contains(&todo.id))
+    }
+
+    /// Remove every completed todo, returning them along with the positions
+    /// they were at. A todo stays in the list while any of its sub-tasks are
+    /// still open.
+    fn clear_completed(&mut self) -> Vec<(usize, ToDo)> {
+        let open: Vec<u64> = self.0.iter()
+            .filter(|todo| todo.is_completed() && self.num_open_sub_tasks(todo.id) > 0)
+            .map(|todo| todo.id)
+            .collect();
+        self.remove_where(|todo| todo.is_completed() && !open.contains(&todo.id))
+    }
+
+    fn remove_where(&mut self, remove: impl Fn(&ToDo) -> bool) -> Vec<(usize, ToDo)> {
+        let mut removed = Vec::new();
+        for (index, todo) in mem::take(&mut self.0).into_iter().enumerate() {
+            if remove(&todo) {
+                removed.push((index, todo));
+            } else {
+                self.0.push(todo);
+            }
+        }
+        removed
+    }
+
+    /// Put todos back where they were removed from. `removed` must be in
+    /// the order `delete` or `clear_completed` returned it.
+    fn restore(&mut self, removed: Vec<(usize, ToDo)>) {
+        for (index, todo) in removed {
+            self.0.insert(index.min(self.0.len()), todo);
+        }
+    }
+
+    fn find_mut(&mut self, id: u64) -> Option<&mut ToDo> {
+        self.0.iter_mut().find(|todo| todo.id == id)
+    }
+
+    /// Ids are never shared by two todos in the same list. They're used as
+    /// the key when rendering the list.
+    fn next_id(&self) -> u64 {
+        self.0.iter().map(|todo| todo.id + 1).max().unwrap_or(0)
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ToDo {
+    id: u64,
+    title: String,
+    status: Status,
+    due: Option<Date>,
+    priority: Priority,
+    tags: Vec<String>,
+    /// The todo this is a sub-task of.
+    parent: Option<u64>,
+    recurrence: Option<Recurrence>,
+    /// Seconds spent working on it with the pomodoro timer.
+    focus_seconds: u64,
+    /// The day it was added. Todos saved before version 10 don't have one.
+    created_on: Option<Date>,
+    /// The day it was last completed, if it's done.
+    completed_on: Option<Date>,
+    /// The days a recurring todo was completed. Completing one moves it on
+    /// rather than leaving it done, so `completed_on` never records them.
+    /// Records synced before version 11 don't have it.
+    #[serde(default)]
+    completions: Vec<Date>,
+}
+
+impl ToDo {
+    /// Completing a recurring todo moves it on to its next due date and
+    /// back to the backlog, leaving it open. One without a due date is
+    /// completed like any other, on `today`. Reopening a todo puts it in the
+    /// backlog.
+    fn toggle(&mut self, today: Date) {
+        match (&self.recurrence, self.due) {
+            (Some(recurrence), Some(due)) if !self.is_completed() => {
+                self.due = Some(recurrence.next_after(due));
+                self.status = Status::Backlog;
+                self.completions.push(today);
+            }
+            _ if self.is_completed() => {
+                self.status = Status::Backlog;
+                self.completed_on = None;
+            }
+            _ => {
+                self.status = Status::Done;
+                self.completed_on = Some(today);
+            }
+        }
+    }
+
+    fn is_completed(&self) -> bool {
+        self.status == Status::Done
+    }
+
+    fn new(title: String) -> ToDo {
+        ToDo {
+            id: 0,
+            title,
+            status: Status::default(),
+            due: None,
+            priority: Priority::default(),
+            tags: Vec::new(),
+            parent: None,
+            recurrence: None,
+            focus_seconds: 0,
+            created_on: None,
+            completed_on: None,
+            completions: Vec::new(),
+        }
+    }
+
+    /// Every day the todo was completed, oldest first: each time a recurring
+    /// todo was, then the day it was completed for good if it has been.
+    fn completion_days(&self) -> impl Iterator<Item = Date> + '_ {
+        self.completions.iter().copied().chain(self.completed_on)
+    }
+
+    /// The status, priority, due date, recurrence, focus time and tags
+    /// written out after the title.
+    fn details(&self) -> String {
+        let mut details = format!(" {} priority", self.priority.label());
+        if self.status == Status::InProgress {
+            details += ", in progress";
+        }
+        if let Some(due) = self.due {
+            details += &format!(", due {due}");
+        }
+        if let Some(recurrence) = &self.recurrence {
+            details += &format!(", repeats {recurrence}");
+        }
+        if self.focus_seconds > 0 {
+            details += &format!(", {} focused", pomodoro::focus_time(self.focus_seconds));
+        }
+        for tag in &self.tags {
+            details += &format!(" #{tag}");
+        }
+        details
+    }
+
+    /// Add tags this todo doesn't already have.
+    fn add_tags(&mut self, tags: Vec<String>) {
+        for tag in tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+    }
+
+    /// A todo is overdue once its due date has passed without it being completed.
+    fn is_overdue(&self, today: Date) -> bool {
+        !self.is_completed() && self.due.is_some_and(|due| due < today)
+    }
+}
+
+/// Which column of the board a todo is in. Only `Done` todos count as
+/// completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    #[default]
+    Backlog,
+    InProgress,
+    Done,
+}
+
+impl Status {
+    const ALL: [Status; 3] = [Status::Backlog, Status::InProgress, Status::Done];
 
-    // listen to an input box for changes
-    let input_element: NodeRef<html::Input> = create_node_ref();
+    fn label(self) -> &'static str {
+        match self {
+            Status::Backlog => "Backlog",
+            Status::InProgress => "In progress",
+            Status::Done => "Done",
+        }
+    }
 
-    let on_submit = move |ev: leptos::ev::SubmitEvent| {
-        ev.prevent_default();
+    /// The column to the left of this one.
+    fn previous(self) -> Option<Status> {
+        Status::ALL.into_iter().take_while(|status| *status != self).last()
+    }
 
-        let value = input_element.get().expect("<input> should be mounted").value();
-        set_todos.update(|todos| todos.new_todo(value));
-    };
+    /// The column to the right of this one.
+    fn next(self) -> Option<Status> {
+        Status::ALL.into_iter().skip_while(|status| *status != self).nth(1)
+    }
+}
 
-    view! {
-        
-        <form on:submit=on_submit>
-            <input type="text"
-                value="task"
-                node_ref=input_element
-            />
-            <input type="submit" value="Submit"/>
-        </form>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
 
-        // `todos.with` is a lot like `todos.get` in that it listens for a change
-        // in the tods signal. Instead of returning a primitive value though, it returns
-        // the return value of some custom logic.
-        <p>"To Dos remaining: " {move || todos.with(|todos| todos.num_remaining())}</p>
+impl Priority {
+    const ALL: [Priority; 3] = [Priority::Low, Priority::Normal, Priority::High];
 
-        // list the todos
-        <ul>
-            {
-                move || todos.with(|todos| {
-                    todos.get()
-                })
-                    .into_iter()
-                    .map(|todo| view! { <li>{todo.title}</li> })
-                    .collect_view()
-            }
-        </ul>
+    fn label(self) -> &'static str {
+        match self {
+            Priority::Low => "Low",
+            Priority::Normal => "Normal",
+            Priority::High => "High",
+        }
     }
+}
 
+/// Why a title was rejected by `ToDos::validate_title`.
+#[derive(Debug, Clone, PartialEq)]
+enum TitleError {
+    Empty,
+    Duplicate(String),
 }
 
+impl fmt::Display for TitleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TitleError::Empty => write!(f, "A to do needs a title."),
+            TitleError::Duplicate(title) => write!(f, "\"{title}\" is already on the list."),
+        }
+    }
+}
 
-// Enclose the logic of the component in these structs to keep the component
-// minimal and focussed only on rendering HTML tags.
-struct ToDos(Vec<ToDo>);
+/// Keyboard shortcuts are ignored while an input has focus.
+fn is_typing(ev: &ev::KeyboardEvent) -> bool {
+    let target = event_target::<web_sys::Element>(ev);
+    takes_keys(&target.tag_name(), &target.get_attribute("type").unwrap_or_default())
+}
 
-impl ToDos {
-    fn num_remaining(&self) -> usize {
-        self.0.iter().filter(|todo| !todo.completed).count()
+/// Whether an element with this tag name and `type` takes key presses.
+/// Checkboxes and radio buttons keep focus after they're clicked, but
+/// shortcuts should still work then.
+fn takes_keys(tag_name: &str, input_type: &str) -> bool {
+    match tag_name {
+        "INPUT" => !input_type.eq_ignore_ascii_case("checkbox") && !input_type.eq_ignore_ascii_case("radio"),
+        "TEXTAREA" | "SELECT" => true,
+        _ => false,
     }
+}
 
-    fn get(&self) -> Vec<ToDo> {
-        self.0.clone()
+/// The path of the `/todo/:list_id` route.
+#[derive(Params, Debug, Clone, PartialEq)]
+struct ListParams {
+    list_id: Option<u64>,
+}
+
+/// The query string of the `/todo/:list_id` route.
+#[derive(Params, Debug, Clone, PartialEq)]
+struct ToDoQuery {
+    filter: Option<Filter>,
+    view: Option<ViewMode>,
+}
+
+/// A link to the list with these options. Options that are already the
+/// default are left out to keep the url short.
+fn query_href(filter: Filter, view: ViewMode) -> String {
+    let mut params = Vec::new();
+    if filter != Filter::default() {
+        params.push(format!("filter={filter}"));
+    }
+    if view != ViewMode::default() {
+        params.push(format!("view={view}"));
+    }
+    format!("?{}", params.join("&"))
+}
+
+/// Whether the todos are shown as a list or as a board.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum ViewMode {
+    #[default]
+    List,
+    Board,
+}
+
+impl ViewMode {
+    const ALL: [ViewMode; 2] = [ViewMode::List, ViewMode::Board];
+
+    fn label(self) -> &'static str {
+        match self {
+            ViewMode::List => "List",
+            ViewMode::Board => "Board",
+        }
+    }
+}
+
+/// Which todos are listed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum Filter {
+    #[default]
+    All,
+    Active,
+    Completed,
+}
+
+impl Filter {
+    const ALL: [Filter; 3] = [Filter::All, Filter::Active, Filter::Completed];
+
+    fn matches(self, todo: &ToDo) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Active => !todo.is_completed(),
+            Filter::Completed => todo.is_completed(),
+        }
     }
 
-    fn new_todo(&mut self, title: String) {
-        self.0.push(ToDo::new(title));
+    fn label(self) -> &'static str {
+        match self {
+            Filter::All => "All",
+            Filter::Active => "Active",
+            Filter::Completed => "Completed",
+        }
     }
 }
 
+/// How the list is filtered and ordered.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct ListOptions {
+    filter: Filter,
+    /// Only todos with every one of these tags are listed.
+    tags: Vec<String>,
+    sort: SortOrder,
+}
 
-#[derive(Debug, Clone, PartialEq)]
-struct ToDo {
-    title: String,
-    completed: bool
+/// How the list is ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum SortOrder {
+    /// The order the user dragged the todos into.
+    #[default]
+    Manual,
+    Created,
+    DueDate,
+    Priority,
 }
 
-impl ToDo {
-    fn toggle(&mut self) {
-        self.completed = !self.completed;
+impl SortOrder {
+    const ALL: [SortOrder; 4] = [SortOrder::Manual, SortOrder::Created, SortOrder::DueDate, SortOrder::Priority];
+
+    fn label(self) -> &'static str {
+        match self {
+            SortOrder::Manual => "Manual",
+            SortOrder::Created => "Created",
+            SortOrder::DueDate => "Due date",
+            SortOrder::Priority => "Priority",
+        }
     }
+}
 
-    fn new(title: String) -> ToDo {
-        ToDo { title: title, completed: false }
+/// Where a moved todo goes relative to the todo it's dropped on.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Place {
+    Before,
+    After,
+}
+
+// The options above are written in urls and `<select>` values as their
+// labels in lowercase with dashes instead of spaces, e.g. `due-date`.
+macro_rules! option_strings {
+    ($($option:ty),*) => {$(
+        impl fmt::Display for $option {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.label().to_lowercase().replace(' ', "-"))
+            }
+        }
+
+        impl FromStr for $option {
+            type Err = UnknownOption;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                <$option>::ALL.into_iter()
+                    .find(|option| option.to_string() == s)
+                    .ok_or_else(|| UnknownOption(s.to_string()))
+            }
+        }
+    )*};
+}
+
+option_strings!(Filter, SortOrder, Priority, ViewMode);
+
+#[derive(Debug, Clone, PartialEq)]
+struct UnknownOption(String);
+
+impl fmt::Display for UnknownOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown option `{}`", self.0)
     }
 }
 
+impl std::error::Error for UnknownOption {}
+
 // tests can be defined here for all the code outside of the component function
 #[cfg(test)]
 mod tests {
@@ -95,16 +2087,16 @@ mod tests {
         let new_todo = ToDo::new("Task".to_string());
         
         assert_eq!(new_todo.title, "Task");
-        assert_eq!(new_todo.completed, false);
+        assert!(!new_todo.is_completed());
     }
 
     #[test]
     fn toggle_todo() {
         let mut new_todo = ToDo::new("Task 2".to_string());
-        new_todo.toggle();
+        new_todo.toggle(today());
         
         assert_eq!(new_todo.title, "Task 2");
-        assert_eq!(new_todo.completed, true);
+        assert!(new_todo.is_completed());
     }
 
     #[test]
@@ -116,7 +2108,7 @@ mod tests {
     #[test]
     fn add_todo() {
         let mut todos = ToDos(Vec::new());
-        todos.new_todo("Task 3".to_string());
+        todos.new_todo("Task 3".to_string()).unwrap();
 
         assert_eq!(todos.num_remaining(), 1);
     }
@@ -124,13 +2116,512 @@ mod tests {
     #[test]
     fn get_todos_list() {
         let mut todos = ToDos(Vec::new());
-        todos.new_todo("Task 4".to_string());
-        todos.new_todo("Task 5".to_string());
+        todos.new_todo("Task 4".to_string()).unwrap();
+        todos.new_todo("Task 5".to_string()).unwrap();
 
         assert_eq!(
             todos.get(), 
-            vec![ToDo::new("Task 4".to_string()), ToDo::new("Task 5".to_string())]
+            vec![
+                ToDo { id: 0, ..ToDo::new("Task 4".to_string()) },
+                ToDo { id: 1, ..ToDo::new("Task 5".to_string()) }
+            ]
+        );
+    }
+
+    #[test]
+    fn ids_are_unique() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("Task 6".to_string()).unwrap();
+        todos.new_todo("Task 7".to_string()).unwrap();
+        todos.delete(0);
+        todos.new_todo("Task 8".to_string()).unwrap();
+
+        let ids: Vec<u64> = todos.get().iter().map(|todo| todo.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn toggle_by_id() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("Task 9".to_string()).unwrap();
+        todos.new_todo("Task 10".to_string()).unwrap();
+        todos.toggle(1, today());
+
+        assert_eq!(todos.num_remaining(), 1);
+        assert!(todos.find(1).unwrap().is_completed());
+    }
+
+    #[test]
+    fn delete_todo() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("Task 11".to_string()).unwrap();
+        todos.new_todo("Task 12".to_string()).unwrap();
+        todos.delete(0);
+
+        assert_eq!(todos.get().len(), 1);
+        assert_eq!(todos.get()[0].title, "Task 12");
+    }
+
+    #[test]
+    fn clear_completed_todos() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("Task 13".to_string()).unwrap();
+        todos.new_todo("Task 14".to_string()).unwrap();
+        todos.toggle(0, today());
+        todos.clear_completed();
+
+        assert_eq!(todos.get().len(), 1);
+        assert_eq!(todos.num_remaining(), 1);
+    }
+
+    #[test]
+    fn filter_todos() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("Task 15".to_string()).unwrap();
+        todos.new_todo("Task 16".to_string()).unwrap();
+        todos.new_todo("Task 17".to_string()).unwrap();
+        todos.toggle(1, today());
+
+        let titles = |filter| todos.filtered(filter).into_iter().map(|todo| todo.title).collect::<Vec<_>>();
+        assert_eq!(titles(Filter::All), vec!["Task 15", "Task 16", "Task 17"]);
+        assert_eq!(titles(Filter::Active), vec!["Task 15", "Task 17"]);
+        assert_eq!(titles(Filter::Completed), vec!["Task 16"]);
+    }
+
+    #[test]
+    fn parse_filter() {
+        assert_eq!("active".parse(), Ok(Filter::Active));
+        assert_eq!(Filter::Completed.to_string().parse(), Ok(Filter::Completed));
+        assert_eq!("done".parse::<Filter>(), Err(UnknownOption("done".to_string())));
+    }
+
+    #[test]
+    fn reject_blank_titles() {
+        let mut todos = ToDos(Vec::new());
+
+        assert_eq!(todos.new_todo("".to_string()), Err(TitleError::Empty));
+        assert_eq!(todos.new_todo("   ".to_string()), Err(TitleError::Empty));
+        assert!(todos.get().is_empty());
+    }
+
+    #[test]
+    fn reject_duplicate_titles() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("Task 18".to_string()).unwrap();
+
+        assert_eq!(
+            todos.new_todo(" task 18 ".to_string()),
+            Err(TitleError::Duplicate("task 18".to_string()))
+        );
+        assert_eq!(todos.get().len(), 1);
+    }
+
+    #[test]
+    fn trim_titles() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("  Task 19 ".to_string()).unwrap();
+
+        assert_eq!(todos.get()[0].title, "Task 19");
+    }
+
+    #[test]
+    fn edit_todo() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("Task 20".to_string()).unwrap();
+        todos.new_todo("Task 21".to_string()).unwrap();
+
+        let previous_title = |result: Result<Option<ToDo>, TitleError>| result.map(|todo| todo.map(|todo| todo.title));
+        assert_eq!(previous_title(todos.edit(0, "Task 22".to_string())), Ok(Some("Task 20".to_string())));
+        assert_eq!(previous_title(todos.edit(0, "TASK 22".to_string())), Ok(Some("Task 22".to_string())));
+        assert_eq!(todos.edit(0, "TASK 22".to_string()), Ok(None));
+        assert_eq!(todos.edit(0, "task 21".to_string()), Err(TitleError::Duplicate("task 21".to_string())));
+        assert_eq!(todos.edit(0, " ".to_string()), Err(TitleError::Empty));
+        assert_eq!(todos.get()[0].title, "TASK 22");
+    }
+
+    #[test]
+    fn parse_sort_and_priority() {
+        assert_eq!(SortOrder::DueDate.to_string(), "due-date");
+        assert_eq!("due-date".parse(), Ok(SortOrder::DueDate));
+        assert_eq!("high".parse(), Ok(Priority::High));
+        assert!("urgent".parse::<Priority>().is_err());
+    }
+
+    fn date(date: &str) -> Date {
+        date.parse().unwrap()
+    }
+
+    /// The day todos are completed on, unless a test cares which day it is.
+    fn today() -> Date {
+        date("2024-06-03")
+    }
+
+    #[test]
+    fn sort_todos() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("No date".to_string()).unwrap();
+        todos.new_todo("Later".to_string()).unwrap().due = Some(date("2024-06-01"));
+        let sooner = todos.new_todo("Sooner".to_string()).unwrap();
+        sooner.due = Some(date("2024-05-01"));
+        sooner.priority = Priority::High;
+        todos.new_todo("Unimportant".to_string()).unwrap().priority = Priority::Low;
+
+        let titles = |sort| todos.sorted(None, &ListOptions { sort, ..Default::default() }).into_iter().map(|todo| todo.title).collect::<Vec<_>>();
+        assert_eq!(titles(SortOrder::Created), vec!["No date", "Later", "Sooner", "Unimportant"]);
+        assert_eq!(titles(SortOrder::DueDate), vec!["Sooner", "Later", "No date", "Unimportant"]);
+        assert_eq!(titles(SortOrder::Priority), vec!["Sooner", "No date", "Later", "Unimportant"]);
+    }
+
+    #[test]
+    fn overdue_todos() {
+        let today = date("2024-05-10");
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("No date".to_string()).unwrap();
+        todos.new_todo("Due today".to_string()).unwrap().due = Some(today);
+        todos.new_todo("Late".to_string()).unwrap().due = Some(date("2024-05-09"));
+        let done = todos.new_todo("Late but done".to_string()).unwrap();
+        done.due = Some(date("2024-01-01"));
+        done.toggle(today);
+
+        assert_eq!(todos.num_overdue(today), 1);
+        assert!(todos.find(2).unwrap().is_overdue(today));
+        assert!(!todos.find(1).unwrap().is_overdue(today));
+    }
+
+    #[test]
+    fn extract_tags() {
+        assert_eq!(
+            ToDos::extract_tags("Email Bob #work #Urgent #work"),
+            ("Email Bob".to_string(), vec!["work".to_string(), "urgent".to_string()])
+        );
+        assert_eq!(ToDos::extract_tags("Fix bug # 4 #no!"), ("Fix bug # 4 #no!".to_string(), vec![]));
+        assert_eq!(ToDos::extract_tags("#home"), ("".to_string(), vec!["home".to_string()]));
+    }
+
+    #[test]
+    fn add_todo_with_tags() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("Buy milk #shopping".to_string()).unwrap();
+
+        assert_eq!(todos.get()[0].title, "Buy milk");
+        assert_eq!(todos.get()[0].tags, vec!["shopping"]);
+        assert_eq!(todos.new_todo("#shopping".to_string()).unwrap_err(), TitleError::Empty);
+        assert_eq!(todos.new_todo("buy milk #food".to_string()).unwrap_err(), TitleError::Duplicate("buy milk".to_string()));
+    }
+
+    #[test]
+    fn edit_adds_tags() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("Buy milk #shopping".to_string()).unwrap();
+        let before = todos.edit(0, "Buy oat milk #vegan #shopping".to_string()).unwrap().unwrap();
+
+        assert_eq!(todos.get()[0].tags, vec!["shopping", "vegan"]);
+        todos.replace(before);
+        assert_eq!(todos.get()[0].title, "Buy milk");
+        assert_eq!(todos.get()[0].tags, vec!["shopping"]);
+    }
+
+    #[test]
+    fn count_tags() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("A #work #urgent".to_string()).unwrap();
+        todos.new_todo("B #home".to_string()).unwrap();
+        todos.new_todo("C #work".to_string()).unwrap();
+
+        assert_eq!(
+            todos.tag_counts(),
+            vec![("home".to_string(), 1), ("urgent".to_string(), 1), ("work".to_string(), 2)]
         );
     }
 
+    #[test]
+    fn filter_by_tags() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("A #work #urgent".to_string()).unwrap();
+        todos.new_todo("B #home".to_string()).unwrap();
+        todos.new_todo("C #work".to_string()).unwrap();
+
+        let titles = |tags: &[&str]| {
+            let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+            todos.sorted(None, &ListOptions { tags, ..Default::default() }).into_iter().map(|todo| todo.title).collect::<Vec<_>>()
+        };
+        assert_eq!(titles(&[]), vec!["A", "B", "C"]);
+        assert_eq!(titles(&["work"]), vec!["A", "C"]);
+        assert_eq!(titles(&["work", "urgent"]), vec!["A"]);
+        assert!(titles(&["work", "home"]).is_empty());
+    }
+
+    fn todos_with_sub_tasks() -> ToDos {
+        // Move
+        // ├ Pack
+        // │ ├ Books
+        // │ └ Plates
+        // └ Book van
+        // Party
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("Move".to_string()).unwrap();
+        todos.new_todo("Pack".to_string()).unwrap().parent = Some(0);
+        todos.new_todo("Books".to_string()).unwrap().parent = Some(1);
+        todos.new_todo("Plates".to_string()).unwrap().parent = Some(1);
+        todos.new_todo("Book van".to_string()).unwrap().parent = Some(0);
+        todos.new_todo("Party".to_string()).unwrap();
+        todos
+    }
+
+    #[test]
+    fn list_sub_tasks() {
+        let todos = todos_with_sub_tasks();
+        let titles = |parent| todos.sorted(parent, &ListOptions::default()).into_iter().map(|todo| todo.title).collect::<Vec<_>>();
+
+        assert_eq!(titles(None), vec!["Move", "Party"]);
+        assert_eq!(titles(Some(0)), vec!["Pack", "Book van"]);
+        assert_eq!(titles(Some(1)), vec!["Books", "Plates"]);
+        assert!(titles(Some(5)).is_empty());
+    }
+
+    #[test]
+    fn sub_task_progress() {
+        let mut todos = todos_with_sub_tasks();
+        todos.toggle(2, today());
+
+        assert_eq!(todos.progress(0), Some((1, 4)));
+        assert_eq!(todos.progress(1), Some((1, 2)));
+        assert_eq!(todos.progress(5), None);
+        assert_eq!(todos.num_open_sub_tasks(0), 3);
+        // sub-tasks count towards the todos remaining
+        assert_eq!(todos.num_remaining(), 5);
+    }
+
+    #[test]
+    fn complete_with_sub_tasks() {
+        let mut todos = todos_with_sub_tasks();
+        todos.toggle(2, today());
+        let before = todos.complete(1, today());
+
+        assert_eq!(before.iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(todos.progress(1), Some((2, 2)));
+        assert_eq!(todos.num_remaining(), 3);
+    }
+
+    #[test]
+    fn delete_with_sub_tasks() {
+        let mut todos = todos_with_sub_tasks();
+        let removed = todos.delete(1);
+
+        assert_eq!(removed.iter().map(|(index, _)| *index).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(todos.get().len(), 3);
+
+        todos.restore(removed);
+        assert_eq!(todos.progress(0), Some((0, 4)));
+    }
+
+    #[test]
+    fn clear_completed_keeps_todos_with_open_sub_tasks() {
+        let mut todos = todos_with_sub_tasks();
+        todos.toggle(0, today());
+        todos.toggle(5, today());
+        todos.clear_completed();
+
+        assert_eq!(todos.get().len(), 5);
+        assert!(todos.find(0).is_some());
+
+        todos.complete(0, today());
+        todos.clear_completed();
+        assert!(todos.get().is_empty());
+    }
+
+    #[test]
+    fn shortcuts_work_after_clicking_a_checkbox() {
+        assert!(takes_keys("INPUT", "text"));
+        assert!(takes_keys("INPUT", ""));
+        assert!(takes_keys("TEXTAREA", ""));
+        assert!(!takes_keys("INPUT", "checkbox"));
+        assert!(!takes_keys("INPUT", "radio"));
+        assert!(!takes_keys("LI", ""));
+    }
+
+    #[test]
+    fn import_todos() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("Existing".to_string()).unwrap();
+
+        let drafts = vec![
+            ToDo { id: 7, ..ToDo::new("Parent".to_string()) },
+            ToDo { id: 8, parent: Some(7), ..ToDo::new("Child #kid".to_string()) },
+            ToDo { id: 9, ..ToDo::new("existing".to_string()) },
+            ToDo { id: 10, parent: Some(9), ..ToDo::new("Orphan".to_string()) },
+        ];
+        let skipped = todos.import(drafts.clone(), false);
+
+        assert_eq!(skipped, vec![TitleError::Duplicate("existing".to_string())]);
+        assert_eq!(todos.get().iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![0, 7, 8, 10]);
+        assert_eq!(todos.find(8).unwrap().parent, Some(7));
+        assert_eq!(todos.find(8).unwrap().tags, vec!["kid"]);
+        assert_eq!(todos.find(10).unwrap().parent, None);
+
+        // an id that's taken is swapped for a new one
+        assert!(todos.import(vec![ToDo { id: 7, ..ToDo::new("Again".to_string()) }], false).is_empty());
+        assert_eq!(todos.get().last().unwrap().id, 11);
+
+        assert!(todos.import(drafts, true).is_empty());
+        assert_eq!(todos.get().len(), 4);
+        assert_eq!(todos.find(10).unwrap().parent, Some(9));
+    }
+
+    #[test]
+    fn renumber_imported_todos() {
+        let mut drafts = vec![
+            ToDo { id: 0, ..ToDo::new("Parent".to_string()) },
+            ToDo { id: 1, parent: Some(0), ..ToDo::new("Child".to_string()) },
+            ToDo { id: 2, parent: Some(5), ..ToDo::new("Orphan".to_string()) },
+        ];
+        ToDos::renumber(&mut drafts, 100);
+
+        let ids: Vec<(u64, Option<u64>)> = drafts.iter().map(|todo| (todo.id, todo.parent)).collect();
+        assert_eq!(ids, vec![(100, None), (101, Some(100)), (102, None)]);
+    }
+
+    #[test]
+    fn toggle_recurring_todo() {
+        let mut todo = ToDo {
+            due: Some(date("2024-06-03")),
+            recurrence: Some(Recurrence::Weekly(vec![Weekday::Monday, Weekday::Wednesday])),
+            ..ToDo::new("Gym".to_string())
+        };
+
+        todo.toggle(today());
+        assert!(!todo.is_completed());
+        assert_eq!(todo.due, Some(date("2024-06-05")));
+        assert_eq!(todo.completions, vec![today()]);
+
+        // without a due date there's nothing to count from
+        todo.due = None;
+        todo.toggle(today());
+        assert!(todo.is_completed());
+    }
+
+    #[test]
+    fn complete_moves_recurring_sub_tasks_on() {
+        let mut todos = todos_with_sub_tasks();
+        let sub_task = todos.find_mut(2).unwrap();
+        sub_task.due = Some(date("2024-06-03"));
+        sub_task.recurrence = Some(Recurrence::Daily);
+
+        todos.complete(0, today());
+        assert!(todos.find(0).unwrap().is_completed());
+        assert!(!todos.find(2).unwrap().is_completed());
+        assert_eq!(todos.find(2).unwrap().due, Some(date("2024-06-04")));
+    }
+
+    #[test]
+    fn move_todos() {
+        let mut todos = ToDos(Vec::new());
+        for title in ["A", "B", "C"] {
+            todos.new_todo(title.to_string()).unwrap();
+        }
+        let titles = |todos: &ToDos, sort| -> Vec<String> {
+            let options = ListOptions { sort, ..ListOptions::default() };
+            todos.sorted(None, &options).into_iter().map(|todo| todo.title).collect()
+        };
+
+        assert_eq!(todos.move_todo(2, 0, Place::Before), Some(2));
+        assert_eq!(titles(&todos, SortOrder::Manual), vec!["C", "A", "B"]);
+        assert_eq!(todos.move_todo(2, 1, Place::After), Some(0));
+        assert_eq!(titles(&todos, SortOrder::Manual), vec!["A", "B", "C"]);
+
+        // the manual order survives sorting by something else
+        assert_eq!(todos.move_todo(0, 1, Place::After), Some(0));
+        assert_eq!(titles(&todos, SortOrder::Created), vec!["A", "B", "C"]);
+        assert_eq!(titles(&todos, SortOrder::Manual), vec!["B", "A", "C"]);
+    }
+
+    #[test]
+    fn neighbours() {
+        let mut todos = todos_with_sub_tasks();
+        let options = ListOptions::default();
+
+        assert_eq!(todos.neighbour(1, &options, Place::After), Some(4));
+        assert_eq!(todos.neighbour(1, &options, Place::Before), None);
+        assert_eq!(todos.neighbour(5, &options, Place::Before), Some(0));
+        assert_eq!(todos.neighbour(3, &options, Place::Before), Some(2));
+
+        // todos that are filtered out are skipped
+        todos.toggle(2, today());
+        let active = ListOptions { filter: Filter::Active, ..ListOptions::default() };
+        assert_eq!(todos.neighbour(3, &active, Place::Before), None);
+    }
+
+    #[test]
+    fn moves_that_change_nothing() {
+        let mut todos = todos_with_sub_tasks();
+
+        assert_eq!(todos.move_todo(0, 0, Place::Before), None);
+        assert_eq!(todos.move_todo(0, 3, Place::Before), None);
+        assert_eq!(todos.move_todo(9, 0, Place::Before), None);
+        // a sub-task can only be moved among its parent's other sub-tasks
+        assert_eq!(todos.move_todo(1, 3, Place::Before), None);
+    }
+
+    #[test]
+    fn move_between_columns() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("A".to_string()).unwrap();
+        let recurring = todos.new_todo("B".to_string()).unwrap();
+        recurring.due = Some(date("2024-06-03"));
+        recurring.recurrence = Some(Recurrence::Daily);
+
+        assert_eq!(todos.set_status(0, Status::InProgress, today()).map(|before| before.status), Some(Status::Backlog));
+        assert_eq!(todos.set_status(0, Status::InProgress, today()), None);
+        assert_eq!(todos.num_remaining(), 2);
+        todos.set_status(0, Status::Done, today());
+        assert_eq!(todos.num_remaining(), 1);
+
+        // finishing a recurring todo sends it back to the backlog for next time
+        todos.set_status(1, Status::InProgress, today());
+        todos.set_status(1, Status::Done, today());
+        assert_eq!(todos.find(1).unwrap().status, Status::Backlog);
+        assert_eq!(todos.find(1).unwrap().due, Some(date("2024-06-04")));
+    }
+
+    #[test]
+    fn completion_dates() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("A".to_string()).unwrap();
+
+        todos.toggle(0, today());
+        assert_eq!(todos.find(0).unwrap().completed_on, Some(today()));
+        todos.toggle(0, today());
+        assert_eq!(todos.find(0).unwrap().completed_on, None);
+
+        // moving a card out of the done column reopens it
+        todos.set_status(0, Status::Done, date("2024-06-04"));
+        assert_eq!(todos.find(0).unwrap().completed_on, Some(date("2024-06-04")));
+        todos.set_status(0, Status::InProgress, today());
+        assert_eq!(todos.find(0).unwrap().completed_on, None);
+    }
+
+    #[test]
+    fn board_columns() {
+        let mut todos = todos_with_sub_tasks();
+        todos.set_status(5, Status::InProgress, today());
+        todos.set_status(3, Status::InProgress, today());
+        todos.toggle(0, today());
+        let options = ListOptions { filter: Filter::Active, ..ListOptions::default() };
+        let ids = |status| todos.column(status, &options).iter().map(|todo| todo.id).collect::<Vec<_>>();
+
+        // sub-tasks stay on their parent's card
+        assert_eq!(ids(Status::Backlog), Vec::<u64>::new());
+        assert_eq!(ids(Status::InProgress), vec![5]);
+        assert_eq!(ids(Status::Done), vec![0]);
+        assert_eq!(Status::Backlog.previous(), None);
+        assert_eq!(Status::InProgress.next(), Some(Status::Done));
+    }
+
+    #[test]
+    fn query_links() {
+        assert_eq!(query_href(Filter::All, ViewMode::List), "?");
+        assert_eq!(query_href(Filter::Active, ViewMode::List), "?filter=active");
+        assert_eq!(query_href(Filter::Completed, ViewMode::Board), "?filter=completed&view=board");
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,289 @@
+use serde::{ Deserialize, Serialize };
+
+use super::{ Date, Place, Priority, Status, TitleError, ToDo, ToDos };
+
+/// Every change that can be made to a list. Actions are plain data, so a
+/// sequence of them can be logged, recorded and replayed with `reduce`.
+/// Actions that can complete a todo carry the day it happened on rather
+/// than reading the clock, so a replay gives the same result on any day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToDoAction {
+    /// Add a todo with the same details as this one. It keeps the draft's
+    /// id unless that's 0 or already taken, when a new one is given out.
+    Add(ToDo),
+    Toggle(u64, Date),
+    /// Complete a todo and all of its sub-tasks.
+    Complete(u64, Date),
+    Edit(u64, String),
+    /// Delete a todo and all of its sub-tasks.
+    Delete(u64),
+    /// Move a todo to just before or after another one.
+    Reorder(u64, u64, Place),
+    /// Move a todo to another column of the board.
+    SetStatus(u64, Status, Date),
+    /// Add this many seconds of pomodoro focus time to a todo.
+    AddFocusTime(u64, u64),
+    SetPriority(u64, Priority),
+    /// Replace a todo's tags.
+    SetTags(u64, Vec<String>),
+    ClearCompleted,
+    /// Add todos read from a file. If the flag is set they replace the
+    /// whole list.
+    Import(Vec<ToDo>, bool),
+    /// Several actions applied as one, e.g. to every selected todo. They're
+    /// undone together, and if one is rejected none of them are applied.
+    Batch(Vec<ToDoAction>),
+}
+
+impl ToDos {
+    /// Apply an action to the list. This is the only way the list is changed
+    /// once the app is running.
+    ///
+    /// Returns what's needed to take the change back, or `None` if the
+    /// action didn't change anything, e.g. because the todo it refers to no
+    /// longer exists.
+    pub fn reduce(&mut self, action: &ToDoAction) -> Result<Option<Inverse>, TitleError> {
+        let inverse = match action {
+            ToDoAction::Add(draft) => Some(Inverse::Delete(self.add(draft.clone())?.id)),
+            ToDoAction::Toggle(id, today) => {
+                self.toggle(*id, *today).map(|before| Inverse::Replace(vec![before]))
+            }
+            ToDoAction::Complete(id, today) => {
+                let before = self.complete(*id, *today);
+                (!before.is_empty()).then_some(Inverse::Replace(before))
+            }
+            ToDoAction::Edit(id, title) => {
+                self.edit(*id, title.clone())?.map(|before| Inverse::Replace(vec![before]))
+            }
+            ToDoAction::Delete(id) => {
+                let removed = self.delete(*id);
+                (!removed.is_empty()).then_some(Inverse::Restore(removed))
+            }
+            ToDoAction::Reorder(id, target, place) => {
+                self.move_todo(*id, *target, *place).map(|from| Inverse::MoveBack(*id, from))
+            }
+            ToDoAction::SetStatus(id, status, today) => {
+                self.set_status(*id, *status, *today).map(|before| Inverse::Replace(vec![before]))
+            }
+            ToDoAction::AddFocusTime(id, seconds) => {
+                self.add_focus_time(*id, *seconds).map(|_| Inverse::RemoveFocusTime(*id, *seconds))
+            }
+            ToDoAction::SetPriority(id, priority) => {
+                self.set_priority(*id, *priority).map(|before| Inverse::Replace(vec![before]))
+            }
+            ToDoAction::SetTags(id, tags) => {
+                self.set_tags(*id, tags.clone()).map(|before| Inverse::Replace(vec![before]))
+            }
+            ToDoAction::ClearCompleted => {
+                let removed = self.clear_completed();
+                (!removed.is_empty()).then_some(Inverse::Restore(removed))
+            }
+            ToDoAction::Import(drafts, replace) => {
+                let before = self.0.clone();
+                self.import(drafts.clone(), *replace);
+                (self.0 != before).then_some(Inverse::Reset(before))
+            }
+            ToDoAction::Batch(actions) => {
+                let mut inverses = Vec::new();
+                for action in actions {
+                    match self.reduce(action) {
+                        Ok(inverse) => inverses.extend(inverse),
+                        Err(err) => {
+                            Inverse::Batch(inverses).apply(self);
+                            return Err(err);
+                        }
+                    }
+                }
+                (!inverses.is_empty()).then_some(Inverse::Batch(inverses))
+            }
+        };
+        Ok(inverse)
+    }
+}
+
+/// The opposite of an action. It's worked out when the action is reduced so
+/// it can hold on to anything the action removed or overwrote.
+///
+/// Focus time is added without going through the history, so putting a
+/// todo back the way it was keeps the focus time it has now.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inverse {
+    Delete(u64),
+    /// Put todos back the way they were.
+    Replace(Vec<ToDo>),
+    /// Put removed todos back at the positions they were taken from.
+    Restore(Vec<(usize, ToDo)>),
+    /// Put a moved todo back at the index it was taken from.
+    MoveBack(u64, usize),
+    /// Put the whole list back the way it was.
+    Reset(Vec<ToDo>),
+    /// Take back focus time added to a todo.
+    RemoveFocusTime(u64, u64),
+    /// Undo a batch, last action first.
+    Batch(Vec<Inverse>),
+}
+
+impl Inverse {
+    pub fn apply(self, todos: &mut ToDos) {
+        match self {
+            Inverse::Delete(id) => { todos.delete(id); }
+            Inverse::Replace(before) => before.into_iter().for_each(|todo| todos.put_back(todo)),
+            Inverse::Restore(removed) => todos.restore(removed),
+            Inverse::MoveBack(id, from) => {
+                let moved = todos.remove_where(|todo| todo.id == id);
+                todos.restore(moved.into_iter().map(|(_, todo)| (from, todo)).collect());
+            }
+            Inverse::Reset(mut before) => {
+                for todo in &mut before {
+                    if let Some(now) = todos.find(todo.id) {
+                        todo.focus_seconds = now.focus_seconds;
+                    }
+                }
+                todos.0 = before;
+            }
+            Inverse::RemoveFocusTime(id, seconds) => {
+                if let Some(todo) = todos.find_mut(id) {
+                    todo.focus_seconds = todo.focus_seconds.saturating_sub(seconds);
+                }
+            }
+            Inverse::Batch(inverses) => inverses.into_iter().rev().for_each(|inverse| inverse.apply(todos)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run a recorded sequence of actions against an empty list. Rejected
+    /// actions are skipped, the same way the app skips them.
+    fn replay(actions: &[ToDoAction]) -> ToDos {
+        let mut todos = ToDos(Vec::new());
+        for action in actions {
+            _ = todos.reduce(action);
+        }
+        todos
+    }
+
+    fn add(title: &str) -> ToDoAction {
+        ToDoAction::Add(ToDo::new(title.to_string()))
+    }
+
+    fn today() -> Date {
+        "2024-06-03".parse().unwrap()
+    }
+
+    fn titles(todos: &ToDos) -> Vec<String> {
+        todos.get().into_iter().map(|todo| todo.title).collect()
+    }
+
+    #[test]
+    fn replay_actions() {
+        let todos = replay(&[
+            add("Buy milk"),
+            add("Walk dog #outside"),
+            add("buy milk"),
+            ToDoAction::Add(ToDo { parent: Some(1), ..ToDo::new("Find lead".to_string()) }),
+            ToDoAction::Toggle(0, today()),
+            ToDoAction::Edit(1, "Walk the dog".to_string()),
+            ToDoAction::Reorder(0, 1, Place::After),
+            ToDoAction::ClearCompleted,
+        ]);
+
+        assert_eq!(titles(&todos), vec!["Walk the dog", "Find lead"]);
+        assert_eq!(todos.get()[0].tags, vec!["outside"]);
+        assert_eq!(todos.get()[1].parent, Some(1));
+    }
+
+    #[test]
+    fn replay_recorded_json() {
+        // a log copied from the browser console, e.g. to reproduce a bug
+        let recorded = r#"[
+            {"add": {"id": 0, "title": "A", "status": "backlog", "due": null, "priority": "high",
+                     "tags": [], "parent": null, "recurrence": null, "focus_seconds": 0,
+                     "created_on": "2024-06-01", "completed_on": null}},
+            {"add": {"id": 0, "title": "B", "status": "backlog", "due": "2024-06-03", "priority": "normal",
+                     "tags": [], "parent": null, "recurrence": "daily", "focus_seconds": 0,
+                     "created_on": null, "completed_on": null}},
+            {"add_focus_time": [1, 1500]},
+            {"toggle": [1, "2024-06-03"]},
+            {"reorder": [1, 0, "before"]},
+            {"delete": 0}
+        ]"#;
+        let actions: Vec<ToDoAction> = serde_json::from_str(recorded).unwrap();
+        let todos = replay(&actions);
+
+        assert_eq!(titles(&todos), vec!["B"]);
+        assert_eq!(todos.get()[0].due, "2024-06-04".parse().ok());
+        assert_eq!(todos.get()[0].focus_seconds, 1500);
+    }
+
+    #[test]
+    fn actions_that_change_nothing() {
+        let mut todos = replay(&[add("A")]);
+
+        assert_eq!(todos.reduce(&ToDoAction::Toggle(5, today())), Ok(None));
+        assert_eq!(todos.reduce(&ToDoAction::Edit(0, "A".to_string())), Ok(None));
+        assert_eq!(todos.reduce(&ToDoAction::Reorder(0, 0, Place::Before)), Ok(None));
+        assert_eq!(todos.reduce(&ToDoAction::ClearCompleted), Ok(None));
+        assert_eq!(todos.reduce(&ToDoAction::AddFocusTime(0, 0)), Ok(None));
+        assert_eq!(todos.reduce(&ToDoAction::SetPriority(0, Priority::Normal)), Ok(None));
+        assert_eq!(todos.reduce(&ToDoAction::Batch(vec![ToDoAction::Delete(5)])), Ok(None));
+        assert_eq!(todos.reduce(&add(" a ")), Err(TitleError::Duplicate("a".to_string())));
+    }
+
+    #[test]
+    fn rejected_batch_changes_nothing() {
+        let mut todos = replay(&[add("A"), add("B")]);
+        let before = todos.get();
+
+        let batch = ToDoAction::Batch(vec![
+            ToDoAction::Delete(0),
+            ToDoAction::Edit(1, "".to_string()),
+            ToDoAction::SetPriority(1, Priority::High),
+        ]);
+        assert_eq!(todos.reduce(&batch), Err(TitleError::Empty));
+        assert_eq!(todos.get(), before);
+    }
+
+    #[test]
+    fn undoing_keeps_focus_time() {
+        let mut todos = replay(&[add("A")]);
+        let edit = todos.reduce(&ToDoAction::Edit(0, "B".to_string())).unwrap().unwrap();
+        let import = todos.reduce(&ToDoAction::Import(vec![ToDo::new("C".to_string())], false)).unwrap().unwrap();
+        todos.reduce(&ToDoAction::AddFocusTime(0, 1500)).unwrap();
+
+        import.apply(&mut todos);
+        edit.apply(&mut todos);
+        assert_eq!(titles(&todos), vec!["A"]);
+        assert_eq!(todos.get()[0].focus_seconds, 1500);
+    }
+
+    #[test]
+    fn inverse_undoes_every_action() {
+        let start = replay(&[add("A"), add("B"), ToDoAction::Toggle(1, today()), add("C")]);
+        let actions = [
+            add("D"),
+            ToDoAction::Toggle(0, today()),
+            ToDoAction::Complete(0, today()),
+            ToDoAction::Edit(0, "E #tag".to_string()),
+            ToDoAction::Delete(1),
+            ToDoAction::Reorder(2, 0, Place::Before),
+            ToDoAction::SetStatus(2, Status::InProgress, today()),
+            ToDoAction::AddFocusTime(2, 300),
+            ToDoAction::SetPriority(0, Priority::High),
+            ToDoAction::SetTags(0, vec!["work".to_string()]),
+            ToDoAction::Batch(vec![ToDoAction::Complete(0, today()), ToDoAction::Delete(2), ToDoAction::Complete(3, today())]),
+            ToDoAction::ClearCompleted,
+            ToDoAction::Import(vec![ToDo::new("F".to_string())], true),
+        ];
+
+        for action in actions {
+            let mut todos = start.clone();
+            let inverse = todos.reduce(&action).unwrap().expect("the action changes the list");
+            inverse.apply(&mut todos);
+            assert_eq!(todos.get(), start.get(), "undoing {action:?}");
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use leptos::*;
+use leptos_router::*;
+
+use super::{ date::{ Date, Month, Weekday }, sync, ListsState, TitleError, ToDo, ToDoAction };
+
+/// The day each week starts on.
+const WEEK_START: Weekday = Weekday::Monday;
+
+/// The todos of every list laid out on a month grid. The month is in the
+/// url (`/todo/calendar?month=2024-06`), so the back button steps back
+/// through the months that were looked at. Clicking a day opens a form to
+/// add a todo due on that day.
+#[component]
+pub fn Calendar() -> impl IntoView {
+
+    let ListsState { lists, set_lists } = expect_context();
+    let today = Date::today();
+
+    // without a month in the url the calendar opens on this month
+    let query = use_query::<CalendarQuery>();
+    let month = create_memo(move |_| {
+        query.with(|query| query.as_ref().ok().and_then(|query| query.month)).unwrap_or(Month::of(today))
+    });
+
+    // only the todos due in the month being shown
+    let days = create_memo(move |_| lists.with(|lists| lists.due_in(month.get())));
+
+    // the day a todo is being added to, if any
+    let (adding_on, set_adding_on) = create_signal(None::<Date>);
+    let (error, set_error) = create_signal(None::<TitleError>);
+    let title_element: NodeRef<html::Input> = create_node_ref();
+    let list_element: NodeRef<html::Select> = create_node_ref();
+
+    let on_add = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+        let Some(due) = adding_on.get_untracked() else { return };
+
+        let title = title_element.get().expect("<input> should be mounted").value();
+        let list_id = list_element.get().expect("<select> should be mounted").value().parse().ok();
+
+        // the todo goes through the same reducer as the ones added on the
+        // list page
+        let action = ToDoAction::Add(ToDo {
+            id: sync::new_id(),
+            due: Some(due),
+            created_on: Some(today),
+            ..ToDo::new(title)
+        });
+        let mut result = Ok(None);
+        set_lists.update(|lists| {
+            if let Some(list) = list_id.and_then(|id| lists.find_mut(id)) {
+                result = list.todos.reduce(&action);
+            }
+        });
+
+        match result {
+            Ok(_) => {
+                set_adding_on.set(None);
+                set_error.set(None);
+            }
+            Err(err) => set_error.set(Some(err)),
+        }
+    };
+
+    let mut weekdays = Weekday::ALL;
+    weekdays.sort_by_key(|day| day.days_since(WEEK_START));
+
+    let cell = move |day: Date| {
+        let todos = days.with(|days| days.get(&day).cloned().unwrap_or_default());
+        let overdue = todos.iter().filter(|(_, todo)| todo.is_overdue(today)).count();
+
+        view! {
+            <td
+                class:other-month=!month.get().contains(day)
+                class:selected=day == today
+                on:click=move |ev| {
+                    // clicking one of the todos opens its list instead
+                    let target = event_target::<web_sys::Element>(&ev);
+                    if target.closest("a").ok().flatten().is_some() {
+                        return;
+                    }
+                    set_adding_on.set(Some(day));
+                    set_error.set(None);
+                }
+            >
+                {day.day()}
+                {(overdue > 0).then(|| view! { " " <span class="badge">{overdue} " overdue"</span> })}
+                <ul>
+                    {todos.into_iter()
+                        .map(|(list_id, todo)| view! {
+                            <li class:completed=todo.is_completed()>
+                                <a href=format!("/todo/{list_id}")>{todo.title}</a>
+                            </li>
+                        })
+                        .collect_view()}
+                </ul>
+            </td>
+        }
+    };
+
+    view! {
+        <h3>{move || month.get().name()}</h3>
+        <p>
+            <a href=move || format!("?month={}", month.get().previous())>"← Previous"</a>
+            " "
+            <a href="?">"Today"</a>
+            " "
+            <a href=move || format!("?month={}", month.get().next())>"Next →"</a>
+            " "
+            {move || {
+                let overdue: usize = days.with(|days| {
+                    days.values().flatten().filter(|(_, todo)| todo.is_overdue(today)).count()
+                });
+                (overdue > 0).then(|| view! { <span class="badge">{overdue} " overdue this month"</span> })
+            }}
+        </p>
+
+        <Show when=move || adding_on.get().is_some()>
+            <form on:submit=on_add>
+                "Add a to do due " {move || adding_on.get().map(|day| day.to_string())} " "
+                <input type="text" placeholder="task" node_ref=title_element/>
+                " to "
+                <select node_ref=list_element>
+                    {lists.with_untracked(|lists| {
+                        lists.0.iter()
+                            .map(|list| view! { <option value=list.id.to_string()>{list.name.clone()}</option> })
+                            .collect_view()
+                    })}
+                </select>
+                <input type="submit" value="Add"/>
+                <button type="button" on:click=move |_| set_adding_on.set(None)>"Cancel"</button>
+                {move || error.get().map(|err| view! { <span class="error">" " {err.to_string()}</span> })}
+            </form>
+        </Show>
+
+        <table class="calendar">
+            <thead>
+                <tr>
+                    {weekdays.into_iter().map(|day| view! { <th>{day.label()}</th> }).collect_view()}
+                </tr>
+            </thead>
+            <tbody>
+                {move || month.get().weeks(WEEK_START)
+                    .into_iter()
+                    .map(|week| view! { <tr>{week.into_iter().map(cell).collect_view()}</tr> })
+                    .collect_view()}
+            </tbody>
+        </table>
+    }
+}
+
+/// The query string of the `/todo/calendar` route.
+#[derive(Params, Debug, Clone, PartialEq)]
+struct CalendarQuery {
+    month: Option<Month>,
+}
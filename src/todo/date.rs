@@ -0,0 +1,402 @@
+use serde::{ Deserialize, Serialize };
+use std::{ fmt, str::FromStr };
+
+/// A calendar day without a time or time zone. Dates are written the same
+/// way `<input type="date">` writes them: `YYYY-MM-DD`.
+///
+/// The fields are ordered so that deriving `Ord` sorts dates chronologically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    /// Returns `None` if the day doesn't exist, like the 30th of February.
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Date> {
+        let valid = (1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month);
+        valid.then_some(Date { year, month, day })
+    }
+
+    pub fn year(self) -> i32 {
+        self.year
+    }
+
+    pub fn month(self) -> u32 {
+        self.month
+    }
+
+    pub fn day(self) -> u32 {
+        self.day
+    }
+
+    /// The current day in the browser's time zone.
+    pub fn today() -> Date {
+        let now = js_sys::Date::new_0();
+        Date {
+            year: now.get_full_year() as i32,
+            month: now.get_month() + 1,
+            day: now.get_date(),
+        }
+    }
+
+    /// The date this many days later, or earlier if `days` is negative.
+    pub fn add_days(self, days: i64) -> Date {
+        Date::from_day_number(self.day_number() + days)
+    }
+
+    /// How many days after `earlier` this date is. Negative if it's before.
+    pub fn days_since(self, earlier: Date) -> i64 {
+        self.day_number() - earlier.day_number()
+    }
+
+    pub fn weekday(self) -> Weekday {
+        // day 0 (1970-01-01) was a Thursday
+        Weekday::ALL[(self.day_number() + 3).rem_euclid(7) as usize]
+    }
+
+    /// The number of days since 1970-01-01, using the proleptic Gregorian
+    /// calendar. See http://howardhinnant.github.io/date_algorithms.html
+    fn day_number(self) -> i64 {
+        let year = self.year as i64 - (self.month <= 2) as i64;
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let month = (self.month as i64 + 9) % 12;
+        let day_of_year = (153 * month + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// The inverse of `day_number`.
+    fn from_day_number(number: i64) -> Date {
+        let number = number + 719_468;
+        let era = number.div_euclid(146_097);
+        let day_of_era = number.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+        let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+        let year = (year_of_era + era * 400 + (month <= 2) as i64) as i32;
+        Date { year, month, day }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    /// How many days after `start` this day comes in a week that begins on
+    /// `start`.
+    pub fn days_since(self, start: Weekday) -> usize {
+        let index = |day| Weekday::ALL.iter().position(|other| *other == day).expect("every day is in ALL");
+        (index(self) + 7 - index(start)) % 7
+    }
+
+    /// The short name, e.g. `Mon`.
+    pub fn label(self) -> &'static str {
+        match self {
+            Weekday::Monday => "Mon",
+            Weekday::Tuesday => "Tue",
+            Weekday::Wednesday => "Wed",
+            Weekday::Thursday => "Thu",
+            Weekday::Friday => "Fri",
+            Weekday::Saturday => "Sat",
+            Weekday::Sunday => "Sun",
+        }
+    }
+}
+
+/// A month of a particular year, written `YYYY-MM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Month {
+    year: i32,
+    month: u32,
+}
+
+impl Month {
+    pub fn new(year: i32, month: u32) -> Option<Month> {
+        (1..=12).contains(&month).then_some(Month { year, month })
+    }
+
+    /// The month a date is in.
+    pub fn of(date: Date) -> Month {
+        Month { year: date.year, month: date.month }
+    }
+
+    pub fn first_day(self) -> Date {
+        Date { year: self.year, month: self.month, day: 1 }
+    }
+
+    pub fn last_day(self) -> Date {
+        Date { year: self.year, month: self.month, day: days_in_month(self.year, self.month) }
+    }
+
+    pub fn contains(self, date: Date) -> bool {
+        Month::of(date) == self
+    }
+
+    pub fn previous(self) -> Month {
+        Month::of(self.first_day().add_days(-1))
+    }
+
+    pub fn next(self) -> Month {
+        Month::of(self.last_day().add_days(1))
+    }
+
+    /// The name of the month and the year, e.g. `June 2024`.
+    pub fn name(self) -> String {
+        const NAMES: [&str; 12] = [
+            "January", "February", "March", "April", "May", "June",
+            "July", "August", "September", "October", "November", "December",
+        ];
+        format!("{} {}", NAMES[self.month as usize - 1], self.year)
+    }
+
+    /// Every week with at least one day in this month, as it's laid out on a
+    /// calendar whose weeks begin on `week_start`. The first and last weeks
+    /// are filled in with days from the months either side.
+    pub fn weeks(self, week_start: Weekday) -> Vec<[Date; 7]> {
+        let first = self.first_day();
+        let mut start = first.add_days(-(first.weekday().days_since(week_start) as i64));
+
+        let mut weeks = Vec::new();
+        while start <= self.last_day() {
+            let mut week = [start; 7];
+            for (offset, day) in week.iter_mut().enumerate() {
+                *day = start.add_days(offset as i64);
+            }
+            weeks.push(week);
+            start = start.add_days(7);
+        }
+        weeks
+    }
+}
+
+impl fmt::Display for Month {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}", self.year, self.month)
+    }
+}
+
+impl FromStr for Month {
+    type Err = InvalidDate;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidDate(s.to_string());
+
+        let (year, month) = s.split_once('-').ok_or_else(invalid)?;
+        let year = year.parse().map_err(|_| invalid())?;
+        let month = month.parse().map_err(|_| invalid())?;
+
+        Month::new(year, month).ok_or_else(invalid)
+    }
+}
+
+pub fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidDate(String);
+
+impl fmt::Display for InvalidDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` is not a date", self.0)
+    }
+}
+
+impl std::error::Error for InvalidDate {}
+
+impl FromStr for Date {
+    type Err = InvalidDate;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidDate(s.to_string());
+
+        let mut parts = s.splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let year = next()?.parse().map_err(|_| invalid())?;
+        let month = next()?.parse().map_err(|_| invalid())?;
+        let day = next()?.parse().map_err(|_| invalid())?;
+
+        Date::new(year, month, day).ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = InvalidDate;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        date.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        let date: Date = "2024-03-09".parse().unwrap();
+
+        assert_eq!(date, Date::new(2024, 3, 9).unwrap());
+        assert_eq!(date.to_string(), "2024-03-09");
+    }
+
+    #[test]
+    fn reject_invalid_dates() {
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("2024-13-01".parse::<Date>().is_err());
+        assert!("2024-04-31".parse::<Date>().is_err());
+        assert!("2024-04".parse::<Date>().is_err());
+        assert!("".parse::<Date>().is_err());
+        assert!("2024-04-xx".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn leap_years() {
+        assert!(is_leap_year(2024));
+        assert!(is_leap_year(2000));
+        assert!(!is_leap_year(1900));
+        assert!(!is_leap_year(2023));
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2023, 2), 28);
+    }
+
+    #[test]
+    fn dates_sort_chronologically() {
+        let mut dates: Vec<Date> = ["2024-01-10", "2023-12-31", "2024-01-02"]
+            .into_iter()
+            .map(|date| date.parse().unwrap())
+            .collect();
+        dates.sort();
+
+        let formatted: Vec<String> = dates.into_iter().map(String::from).collect();
+        assert_eq!(formatted, vec!["2023-12-31", "2024-01-02", "2024-01-10"]);
+    }
+
+    #[test]
+    fn serialize_as_string() {
+        let date = Date::new(2024, 5, 1).unwrap();
+
+        assert_eq!(serde_json::to_string(&date).unwrap(), r#""2024-05-01""#);
+        assert_eq!(serde_json::from_str::<Date>(r#""2024-05-01""#).unwrap(), date);
+        assert!(serde_json::from_str::<Date>(r#""2024-05-32""#).is_err());
+    }
+
+    #[test]
+    fn add_days() {
+        let date = Date::new(2024, 2, 28).unwrap();
+
+        assert_eq!(date.add_days(1), Date::new(2024, 2, 29).unwrap());
+        assert_eq!(date.add_days(2), Date::new(2024, 3, 1).unwrap());
+        assert_eq!(date.add_days(366), Date::new(2025, 2, 28).unwrap());
+        assert_eq!(date.add_days(-59), Date::new(2023, 12, 31).unwrap());
+        assert_eq!(Date::new(1970, 1, 1).unwrap().add_days(-1), Date::new(1969, 12, 31).unwrap());
+        assert_eq!(date.add_days(366).days_since(date), 366);
+        assert_eq!(date.days_since(date.add_days(1)), -1);
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(Date::new(1970, 1, 1).unwrap().weekday(), Weekday::Thursday);
+        assert_eq!(Date::new(2024, 2, 29).unwrap().weekday(), Weekday::Thursday);
+        assert_eq!(Date::new(2024, 6, 2).unwrap().weekday(), Weekday::Sunday);
+        assert_eq!(Date::new(1969, 12, 29).unwrap().weekday(), Weekday::Monday);
+    }
+
+    #[test]
+    fn days_since_week_start() {
+        assert_eq!(Weekday::Monday.days_since(Weekday::Monday), 0);
+        assert_eq!(Weekday::Sunday.days_since(Weekday::Monday), 6);
+        assert_eq!(Weekday::Monday.days_since(Weekday::Sunday), 1);
+    }
+
+    #[test]
+    fn parse_and_step_months() {
+        let month: Month = "2024-12".parse().unwrap();
+
+        assert_eq!(month.to_string(), "2024-12");
+        assert_eq!(month.next().to_string(), "2025-01");
+        assert_eq!(month.previous().previous().to_string(), "2024-10");
+        assert_eq!(month.name(), "December 2024");
+        assert!("2024-13".parse::<Month>().is_err());
+        assert!("2024".parse::<Month>().is_err());
+    }
+
+    #[test]
+    fn month_bounds() {
+        let february = Month::new(2024, 2).unwrap();
+
+        assert_eq!(february.last_day(), Date::new(2024, 2, 29).unwrap());
+        assert_eq!(Month::new(2023, 2).unwrap().last_day(), Date::new(2023, 2, 28).unwrap());
+        assert!(february.contains(Date::new(2024, 2, 29).unwrap()));
+        assert!(!february.contains(Date::new(2024, 3, 1).unwrap()));
+        assert_eq!(Month::of(Date::new(2024, 7, 14).unwrap()), Month::new(2024, 7).unwrap());
+    }
+
+    #[test]
+    fn weeks_in_month() {
+        // February 2026 starts on a Sunday and has exactly four weeks
+        let february = Month::new(2026, 2).unwrap();
+        assert_eq!(february.weeks(Weekday::Sunday).len(), 4);
+        assert_eq!(february.weeks(Weekday::Monday).len(), 5);
+
+        // June 2024 starts on a Saturday and ends on a Sunday
+        let weeks = Month::new(2024, 6).unwrap().weeks(Weekday::Monday);
+        assert_eq!(weeks.len(), 5);
+        assert_eq!(weeks[0][0], Date::new(2024, 5, 27).unwrap());
+        assert_eq!(weeks[0][5], Date::new(2024, 6, 1).unwrap());
+        assert_eq!(weeks[4][6], Date::new(2024, 6, 30).unwrap());
+        assert!(weeks.iter().all(|week| week[0].weekday() == Weekday::Monday));
+
+        // and takes six weeks when they start on Sunday
+        let weeks = Month::new(2024, 6).unwrap().weeks(Weekday::Sunday);
+        assert_eq!(weeks.len(), 6);
+        assert_eq!(weeks[0][0], Date::new(2024, 5, 26).unwrap());
+        assert_eq!(weeks[5][0], Date::new(2024, 6, 30).unwrap());
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::VecDeque;
+
+use super::{ action::{ Inverse, ToDoAction }, TitleError, ToDos };
+
+/// A bounded log of the actions applied to a list, so they can be undone and
+/// redone. Once `limit` actions have been recorded the oldest one is
+/// forgotten.
+pub struct History {
+    undo: VecDeque<(ToDoAction, Inverse)>,
+    redo: Vec<ToDoAction>,
+    limit: usize,
+}
+
+impl History {
+    pub fn new(limit: usize) -> History {
+        History { undo: VecDeque::new(), redo: Vec::new(), limit }
+    }
+
+    /// Apply a new action. Anything that was undone can't be redone anymore.
+    /// Nothing is recorded if the action was rejected.
+    pub fn execute(&mut self, todos: &mut ToDos, action: ToDoAction) -> Result<(), TitleError> {
+        if let Some(inverse) = todos.reduce(&action)? {
+            self.record(action, inverse);
+            self.redo.clear();
+        }
+        Ok(())
+    }
+
+    pub fn undo(&mut self, todos: &mut ToDos) {
+        if let Some((action, inverse)) = self.undo.pop_back() {
+            inverse.apply(todos);
+            self.redo.push(action);
+        }
+    }
+
+    pub fn redo(&mut self, todos: &mut ToDos) {
+        if let Some(action) = self.redo.pop() {
+            if let Ok(Some(inverse)) = todos.reduce(&action) {
+                self.record(action, inverse);
+            }
+        }
+    }
+
+    /// Forget everything that could be undone or redone.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    fn record(&mut self, action: ToDoAction, inverse: Inverse) {
+        self.undo.push_back((action, inverse));
+        if self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{ Date, Place, Recurrence, ToDo };
+
+    fn add(title: &str) -> ToDoAction {
+        ToDoAction::Add(ToDo::new(title.to_string()))
+    }
+
+    fn today() -> Date {
+        "2024-06-03".parse().unwrap()
+    }
+
+    fn titles(todos: &ToDos) -> Vec<String> {
+        todos.get().into_iter().map(|todo| todo.title).collect()
+    }
+
+    #[test]
+    fn undo_and_redo_add() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(10);
+        history.execute(&mut todos, add("Task 1")).unwrap();
+
+        history.undo(&mut todos);
+        assert!(todos.get().is_empty());
+        assert!(history.can_redo());
+
+        history.redo(&mut todos);
+        assert_eq!(titles(&todos), vec!["Task 1"]);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn clear_forgets_undo_and_redo() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(10);
+        history.execute(&mut todos, ToDoAction::Import(vec![ToDo::new("A".to_string())], false)).unwrap();
+        history.execute(&mut todos, add("B")).unwrap();
+        history.undo(&mut todos);
+
+        history.clear();
+        assert!(!history.can_undo());
+        assert!(!history.can_redo());
+        history.undo(&mut todos);
+        assert_eq!(titles(&todos), vec!["A"]);
+    }
+
+    #[test]
+    fn undo_toggle_and_edit() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(10);
+        history.execute(&mut todos, add("Task 2")).unwrap();
+        history.execute(&mut todos, ToDoAction::Toggle(0, today())).unwrap();
+        history.execute(&mut todos, ToDoAction::Edit(0, "Task 3".to_string())).unwrap();
+
+        history.undo(&mut todos);
+        assert_eq!(titles(&todos), vec!["Task 2"]);
+        history.undo(&mut todos);
+        assert_eq!(todos.num_remaining(), 1);
+    }
+
+    #[test]
+    fn undo_delete_restores_position() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(10);
+        for title in ["A", "B", "C"] {
+            history.execute(&mut todos, add(title)).unwrap();
+        }
+        history.execute(&mut todos, ToDoAction::Delete(1)).unwrap();
+        assert_eq!(titles(&todos), vec!["A", "C"]);
+
+        history.undo(&mut todos);
+        assert_eq!(titles(&todos), vec!["A", "B", "C"]);
+        assert_eq!(todos.get()[1].id, 1);
+    }
+
+    #[test]
+    fn undo_clear_completed() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(10);
+        for title in ["A", "B", "C", "D"] {
+            history.execute(&mut todos, add(title)).unwrap();
+        }
+        history.execute(&mut todos, ToDoAction::Toggle(0, today())).unwrap();
+        history.execute(&mut todos, ToDoAction::Toggle(2, today())).unwrap();
+        history.execute(&mut todos, ToDoAction::ClearCompleted).unwrap();
+        assert_eq!(titles(&todos), vec!["B", "D"]);
+
+        history.undo(&mut todos);
+        assert_eq!(titles(&todos), vec!["A", "B", "C", "D"]);
+        assert_eq!(todos.num_remaining(), 2);
+    }
+
+    #[test]
+    fn new_action_clears_redo() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(10);
+        history.execute(&mut todos, add("A")).unwrap();
+        history.undo(&mut todos);
+        history.execute(&mut todos, add("B")).unwrap();
+
+        assert!(!history.can_redo());
+        assert_eq!(titles(&todos), vec!["B"]);
+    }
+
+    #[test]
+    fn actions_that_change_nothing_are_not_recorded() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(10);
+        history.execute(&mut todos, ToDoAction::Delete(7)).unwrap();
+        history.execute(&mut todos, ToDoAction::ClearCompleted).unwrap();
+
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn rejected_actions_are_not_recorded() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(10);
+        history.execute(&mut todos, add("A")).unwrap();
+
+        assert_eq!(history.execute(&mut todos, add("a")), Err(TitleError::Duplicate("a".to_string())));
+        assert_eq!(history.execute(&mut todos, ToDoAction::Edit(0, "".to_string())), Err(TitleError::Empty));
+
+        history.undo(&mut todos);
+        assert!(!history.can_undo());
+        assert!(todos.get().is_empty());
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(2);
+        for title in ["A", "B", "C"] {
+            history.execute(&mut todos, add(title)).unwrap();
+        }
+
+        history.undo(&mut todos);
+        history.undo(&mut todos);
+        history.undo(&mut todos);
+        assert_eq!(titles(&todos), vec!["A"]);
+    }
+
+    #[test]
+    fn undo_edit_removes_new_tags() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(10);
+        history.execute(&mut todos, add("Task 4 #home")).unwrap();
+        history.execute(&mut todos, ToDoAction::Edit(0, "Task 5 #work".to_string())).unwrap();
+        assert_eq!(todos.get()[0].tags, vec!["home", "work"]);
+
+        history.undo(&mut todos);
+        assert_eq!(titles(&todos), vec!["Task 4"]);
+        assert_eq!(todos.get()[0].tags, vec!["home"]);
+    }
+
+    #[test]
+    fn undo_complete_and_delete_with_sub_tasks() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(10);
+        history.execute(&mut todos, add("Parent")).unwrap();
+        history.execute(&mut todos, ToDoAction::Add(ToDo { parent: Some(0), ..ToDo::new("Child".to_string()) })).unwrap();
+        history.execute(&mut todos, add("Other")).unwrap();
+
+        history.execute(&mut todos, ToDoAction::Complete(0, today())).unwrap();
+        assert_eq!(todos.num_remaining(), 1);
+        history.undo(&mut todos);
+        assert_eq!(todos.num_remaining(), 3);
+
+        history.execute(&mut todos, ToDoAction::Delete(0)).unwrap();
+        assert_eq!(titles(&todos), vec!["Other"]);
+        history.undo(&mut todos);
+        assert_eq!(titles(&todos), vec!["Parent", "Child", "Other"]);
+        assert_eq!(todos.get()[1].parent, Some(0));
+    }
+
+    #[test]
+    fn undo_import() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(10);
+        history.execute(&mut todos, add("A")).unwrap();
+
+        history.execute(&mut todos, ToDoAction::Import(vec![ToDo::new("B".to_string())], true)).unwrap();
+        assert_eq!(titles(&todos), vec!["B"]);
+
+        history.undo(&mut todos);
+        assert_eq!(titles(&todos), vec!["A"]);
+        history.redo(&mut todos);
+        assert_eq!(titles(&todos), vec!["B"]);
+    }
+
+    #[test]
+    fn undo_toggle_of_recurring_todo() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(10);
+        let due = "2024-06-03".parse().ok();
+        let draft = ToDo { due, recurrence: Some(Recurrence::Daily), ..ToDo::new("Water plants".to_string()) };
+        history.execute(&mut todos, ToDoAction::Add(draft)).unwrap();
+
+        history.execute(&mut todos, ToDoAction::Toggle(0, today())).unwrap();
+        assert_eq!(todos.get()[0].due, "2024-06-04".parse().ok());
+
+        history.undo(&mut todos);
+        assert_eq!(todos.get()[0].due, due);
+    }
+
+    #[test]
+    fn undo_move() {
+        let mut todos = ToDos(Vec::new());
+        let mut history = History::new(10);
+        for title in ["A", "B", "C"] {
+            history.execute(&mut todos, add(title)).unwrap();
+        }
+
+        history.execute(&mut todos, ToDoAction::Reorder(0, 2, Place::After)).unwrap();
+        assert_eq!(titles(&todos), vec!["B", "C", "A"]);
+
+        history.undo(&mut todos);
+        assert_eq!(titles(&todos), vec!["A", "B", "C"]);
+        history.redo(&mut todos);
+        assert_eq!(titles(&todos), vec!["B", "C", "A"]);
+    }
+}
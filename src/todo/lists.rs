@@ -0,0 +1,180 @@
+use serde::{ Deserialize, Serialize };
+use std::{ collections::BTreeMap, fmt };
+
+use super::{ date::{ Date, Month }, ToDo, ToDos };
+
+/// A named to-do list. Its id is the one in the url, `/todo/:list_id`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ToDoList {
+    pub id: u64,
+    pub name: String,
+    pub todos: ToDos,
+}
+
+/// Every to-do list, in the order they're shown in the sidebar.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Lists(pub Vec<ToDoList>);
+
+impl Default for Lists {
+    /// A first visit starts with one empty list.
+    fn default() -> Self {
+        Lists(vec![ToDoList { id: 0, name: "To Do".to_string(), todos: ToDos(Vec::new()) }])
+    }
+}
+
+impl Lists {
+    pub fn find(&self, id: u64) -> Option<&ToDoList> {
+        self.0.iter().find(|list| list.id == id)
+    }
+
+    pub fn find_mut(&mut self, id: u64) -> Option<&mut ToDoList> {
+        self.0.iter_mut().find(|list| list.id == id)
+    }
+
+    /// Just the list with this id, e.g. to export it on its own.
+    pub fn only(&self, id: u64) -> Lists {
+        Lists(self.find(id).cloned().into_iter().collect())
+    }
+
+    /// Every todo from every list that's due in `month`, grouped by day.
+    /// Each one comes with the id of the list it's on.
+    pub fn due_in(&self, month: Month) -> BTreeMap<Date, Vec<(u64, ToDo)>> {
+        let mut days: BTreeMap<Date, Vec<(u64, ToDo)>> = BTreeMap::new();
+        for list in &self.0 {
+            for todo in &list.todos.0 {
+                if let Some(due) = todo.due.filter(|due| month.contains(*due)) {
+                    days.entry(due).or_default().push((list.id, todo.clone()));
+                }
+            }
+        }
+        days
+    }
+
+    /// Add an empty list. The id comes from the caller, `sync::new_id` in
+    /// the app, so it's the same on every client. The name is checked with
+    /// `validate_name`.
+    pub fn new_list(&mut self, id: u64, name: String) -> Result<(), NameError> {
+        let name = self.validate_name(&name, None)?;
+        self.0.push(ToDoList { id, name, todos: ToDos(Vec::new()) });
+        Ok(())
+    }
+
+    /// Returns false if there's no list with this id.
+    pub fn rename(&mut self, id: u64, name: String) -> Result<bool, NameError> {
+        let name = self.validate_name(&name, Some(id))?;
+        let Some(list) = self.find_mut(id) else { return Ok(false) };
+        list.name = name;
+        Ok(true)
+    }
+
+    /// Remove a list along with its todos and return it.
+    pub fn delete(&mut self, id: u64) -> Option<ToDoList> {
+        let index = self.0.iter().position(|list| list.id == id)?;
+        Some(self.0.remove(index))
+    }
+
+    /// Names follow the same rules as todo titles: they can't be blank or
+    /// the same as another list's name (ignoring case). Returns the name
+    /// with surrounding whitespace trimmed.
+    fn validate_name(&self, name: &str, id: Option<u64>) -> Result<String, NameError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(NameError::Empty);
+        }
+
+        let lowercase = name.to_lowercase();
+        let duplicate = self.0.iter()
+            .any(|list| Some(list.id) != id && list.name.to_lowercase() == lowercase);
+        if duplicate {
+            return Err(NameError::Duplicate(name.to_string()));
+        }
+
+        Ok(name.to_string())
+    }
+}
+
+/// Why a list name was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NameError {
+    Empty,
+    Duplicate(String),
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::Empty => write!(f, "A list needs a name."),
+            NameError::Duplicate(name) => write!(f, "There's already a list called \"{name}\"."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(lists: &Lists) -> Vec<&str> {
+        lists.0.iter().map(|list| list.name.as_str()).collect()
+    }
+
+    #[test]
+    fn new_lists() {
+        let mut lists = Lists::default();
+
+        assert_eq!(lists.new_list(1, " Shopping ".to_string()), Ok(()));
+        assert_eq!(lists.new_list(2, "Work".to_string()), Ok(()));
+        assert_eq!(names(&lists), vec!["To Do", "Shopping", "Work"]);
+        assert_eq!(lists.find(2).map(|list| list.name.as_str()), Some("Work"));
+    }
+
+    #[test]
+    fn reject_blank_and_duplicate_names() {
+        let mut lists = Lists::default();
+
+        assert_eq!(lists.new_list(1, "  ".to_string()), Err(NameError::Empty));
+        assert_eq!(lists.new_list(1, "to do".to_string()), Err(NameError::Duplicate("to do".to_string())));
+        assert_eq!(lists.0.len(), 1);
+    }
+
+    #[test]
+    fn rename_list() {
+        let mut lists = Lists::default();
+        lists.new_list(1, "Work".to_string()).unwrap();
+
+        assert_eq!(lists.rename(0, "Home".to_string()), Ok(true));
+        assert_eq!(lists.rename(0, "HOME".to_string()), Ok(true));
+        assert_eq!(lists.rename(0, "work".to_string()), Err(NameError::Duplicate("work".to_string())));
+        assert_eq!(lists.rename(5, "Other".to_string()), Ok(false));
+        assert_eq!(names(&lists), vec!["HOME", "Work"]);
+    }
+
+    #[test]
+    fn delete_list() {
+        let mut lists = Lists::default();
+        lists.new_list(1, "Work".to_string()).unwrap();
+
+        assert_eq!(lists.delete(0).map(|list| list.name), Some("To Do".to_string()));
+        assert!(lists.delete(0).is_none());
+        assert!(lists.find(0).is_none());
+    }
+
+    #[test]
+    fn todos_due_in_a_month() {
+        let mut lists = Lists::default();
+        let work = 1;
+        lists.new_list(work, "Work".to_string()).unwrap();
+        let due = |date: &str| date.parse::<Date>().ok();
+        lists.find_mut(0).unwrap().todos.new_todo("Rent".to_string()).unwrap().due = due("2024-06-01");
+        lists.find_mut(0).unwrap().todos.new_todo("Undated".to_string()).unwrap();
+        lists.find_mut(work).unwrap().todos.new_todo("Report".to_string()).unwrap().due = due("2024-06-01");
+        lists.find_mut(work).unwrap().todos.new_todo("Later".to_string()).unwrap().due = due("2024-07-01");
+
+        let days = lists.due_in("2024-06".parse().unwrap());
+        let first: Vec<(u64, &str)> = days[&due("2024-06-01").unwrap()].iter()
+            .map(|(list, todo)| (*list, todo.title.as_str()))
+            .collect();
+
+        assert_eq!(days.len(), 1);
+        assert_eq!(first, vec![(0, "Rent"), (work, "Report")]);
+    }
+}
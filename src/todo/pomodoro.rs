@@ -0,0 +1,212 @@
+use std::mem;
+
+/// How long each phase lasts, in seconds.
+pub const WORK: u32 = 25 * 60;
+pub const SHORT_BREAK: u32 = 5 * 60;
+pub const LONG_BREAK: u32 = 15 * 60;
+
+/// Every fourth break is a long one.
+const LONG_BREAK_EVERY: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    pub fn length(self) -> u32 {
+        match self {
+            Phase::Work => WORK,
+            Phase::ShortBreak => SHORT_BREAK,
+            Phase::LongBreak => LONG_BREAK,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Phase::Work => "Focus",
+            Phase::ShortBreak => "Short break",
+            Phase::LongBreak => "Long break",
+        }
+    }
+}
+
+/// A pomodoro timer running on one todo. It counts down work intervals with
+/// breaks in between and keeps track of the focus time that hasn't been
+/// added to the todo yet. The timer doesn't run itself, something has to
+/// call `tick` once a second.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pomodoro {
+    todo: u64,
+    phase: Phase,
+    /// Seconds into the current phase.
+    elapsed: u32,
+    /// How many work intervals have finished.
+    intervals: u32,
+    /// Seconds of work not yet handed out by `take_focus_time`.
+    unrecorded: u64,
+    paused: bool,
+}
+
+impl Pomodoro {
+    /// Start with a work interval on this todo.
+    pub fn new(todo: u64) -> Pomodoro {
+        Pomodoro { todo, phase: Phase::Work, elapsed: 0, intervals: 0, unrecorded: 0, paused: false }
+    }
+
+    /// The id of the todo being worked on.
+    pub fn todo(&self) -> u64 {
+        self.todo
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Seconds left in the current phase.
+    pub fn remaining(&self) -> u32 {
+        self.phase.length() - self.elapsed
+    }
+
+    /// How far through the current phase the timer is.
+    pub fn percent_done(&self) -> i32 {
+        (self.elapsed * 100 / self.phase.length()) as i32
+    }
+
+    /// One second has passed. Moves on to the next phase once the current
+    /// one runs out.
+    pub fn tick(&mut self) {
+        if self.paused {
+            return;
+        }
+        self.elapsed += 1;
+        if self.phase == Phase::Work {
+            self.unrecorded += 1;
+        }
+        if self.elapsed >= self.phase.length() {
+            self.skip();
+        }
+    }
+
+    /// End the current phase early and start the next one.
+    pub fn skip(&mut self) {
+        self.phase = match self.phase {
+            Phase::Work => {
+                self.intervals += 1;
+                if self.intervals.is_multiple_of(LONG_BREAK_EVERY) { Phase::LongBreak } else { Phase::ShortBreak }
+            }
+            Phase::ShortBreak | Phase::LongBreak => Phase::Work,
+        };
+        self.elapsed = 0;
+    }
+
+    /// The focus time of a work interval that has just ended, if there is
+    /// some that hasn't been taken yet.
+    pub fn finished_focus(&mut self) -> Option<u64> {
+        (self.phase != Phase::Work && self.unrecorded > 0).then(|| self.take_focus_time())
+    }
+
+    /// All the focus time that hasn't been taken yet, e.g. when the timer
+    /// is stopped halfway through a work interval.
+    pub fn take_focus_time(&mut self) -> u64 {
+        mem::take(&mut self.unrecorded)
+    }
+}
+
+/// A countdown, e.g. `24:05`.
+pub fn clock(seconds: u32) -> String {
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Time spent on a todo, rounded down to the minute, e.g. `1h 05m`.
+pub fn focus_time(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    if minutes < 60 {
+        format!("{minutes}m")
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(pomodoro: &mut Pomodoro, seconds: u32) {
+        for _ in 0..seconds {
+            pomodoro.tick();
+        }
+    }
+
+    #[test]
+    fn count_down_a_work_interval() {
+        let mut pomodoro = Pomodoro::new(3);
+        run(&mut pomodoro, 60);
+
+        assert_eq!(pomodoro.phase(), Phase::Work);
+        assert_eq!(pomodoro.remaining(), WORK - 60);
+        assert_eq!(pomodoro.percent_done(), 4);
+        assert_eq!(pomodoro.finished_focus(), None);
+
+        run(&mut pomodoro, WORK - 60);
+        assert_eq!(pomodoro.phase(), Phase::ShortBreak);
+        assert_eq!(pomodoro.remaining(), SHORT_BREAK);
+        assert_eq!(pomodoro.finished_focus(), Some(WORK as u64));
+        assert_eq!(pomodoro.finished_focus(), None);
+    }
+
+    #[test]
+    fn breaks_are_not_focus_time() {
+        let mut pomodoro = Pomodoro::new(3);
+        pomodoro.skip();
+        run(&mut pomodoro, 30);
+
+        assert_eq!(pomodoro.take_focus_time(), 0);
+        run(&mut pomodoro, SHORT_BREAK);
+        assert_eq!(pomodoro.phase(), Phase::Work);
+    }
+
+    #[test]
+    fn every_fourth_break_is_long() {
+        let mut pomodoro = Pomodoro::new(3);
+        let mut breaks = Vec::new();
+        for _ in 0..4 {
+            pomodoro.skip();
+            breaks.push(pomodoro.phase());
+            pomodoro.skip();
+        }
+
+        assert_eq!(breaks, vec![Phase::ShortBreak, Phase::ShortBreak, Phase::ShortBreak, Phase::LongBreak]);
+    }
+
+    #[test]
+    fn pause() {
+        let mut pomodoro = Pomodoro::new(3);
+        run(&mut pomodoro, 10);
+        pomodoro.toggle_pause();
+        run(&mut pomodoro, 10);
+
+        assert!(pomodoro.is_paused());
+        assert_eq!(pomodoro.remaining(), WORK - 10);
+        assert_eq!(pomodoro.take_focus_time(), 10);
+    }
+
+    #[test]
+    fn format_times() {
+        assert_eq!(clock(WORK), "25:00");
+        assert_eq!(clock(65), "01:05");
+        assert_eq!(focus_time(59), "0m");
+        assert_eq!(focus_time(25 * 60), "25m");
+        assert_eq!(focus_time(65 * 60 + 30), "1h 05m");
+    }
+}
@@ -0,0 +1,137 @@
+use serde::{ Deserialize, Serialize };
+use std::fmt;
+
+use super::date::{ days_in_month, Date, Weekday };
+
+/// How often a todo comes back. Completing a recurring todo moves its due
+/// date to the next occurrence instead of marking it done.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recurrence {
+    Daily,
+    /// Every week on each of these days. With no days it's once a week on
+    /// whatever day the todo was due.
+    Weekly(Vec<Weekday>),
+    /// Every month on this day. Months that are too short use their last day.
+    Monthly(u32),
+    /// Every this many days. Zero counts as one.
+    EveryNDays(u32),
+}
+
+impl Recurrence {
+    /// The first day after `date` that the todo is due again.
+    pub fn next_after(&self, date: Date) -> Date {
+        match self {
+            Recurrence::Daily => date.add_days(1),
+            Recurrence::Weekly(days) if days.is_empty() => date.add_days(7),
+            Recurrence::Weekly(days) => (1..=7)
+                .map(|n| date.add_days(n))
+                .find(|next| days.contains(&next.weekday()))
+                .expect("every weekday comes round within a week"),
+            Recurrence::Monthly(day) => {
+                let this_month = on_day(date.year(), date.month(), *day);
+                if this_month > date {
+                    this_month
+                } else if date.month() == 12 {
+                    on_day(date.year() + 1, 1, *day)
+                } else {
+                    on_day(date.year(), date.month() + 1, *day)
+                }
+            }
+            Recurrence::EveryNDays(days) => date.add_days((*days).max(1) as i64),
+        }
+    }
+
+    /// The first day from `date` onwards that the todo is due. Used to give
+    /// a recurring todo its first due date when it doesn't have one.
+    pub fn first_from(&self, date: Date) -> Date {
+        if self.falls_on(date) { date } else { self.next_after(date) }
+    }
+
+    fn falls_on(&self, date: Date) -> bool {
+        match self {
+            Recurrence::Weekly(days) if !days.is_empty() => days.contains(&date.weekday()),
+            Recurrence::Monthly(day) => on_day(date.year(), date.month(), *day) == date,
+            _ => true,
+        }
+    }
+}
+
+/// The given day of a month, moved back to the last day if the month is
+/// too short.
+fn on_day(year: i32, month: u32, day: u32) -> Date {
+    let day = day.clamp(1, days_in_month(year, month));
+    Date::new(year, month, day).expect("the day was clamped to the month")
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly(days) if days.is_empty() => write!(f, "weekly"),
+            Recurrence::Weekly(days) => {
+                let days: Vec<&str> = days.iter().map(|day| day.label()).collect();
+                write!(f, "weekly on {}", days.join(", "))
+            }
+            Recurrence::Monthly(day) => write!(f, "monthly on day {day}"),
+            Recurrence::EveryNDays(days) => write!(f, "every {} days", (*days).max(1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> Date {
+        date.parse().unwrap()
+    }
+
+    #[test]
+    fn daily() {
+        assert_eq!(Recurrence::Daily.next_after(date("2024-12-31")), date("2025-01-01"));
+    }
+
+    #[test]
+    fn weekly_on_weekdays() {
+        let recurrence = Recurrence::Weekly(vec![Weekday::Monday, Weekday::Thursday]);
+
+        // 2024-06-03 is a Monday
+        assert_eq!(recurrence.next_after(date("2024-06-03")), date("2024-06-06"));
+        assert_eq!(recurrence.next_after(date("2024-06-06")), date("2024-06-10"));
+        assert_eq!(recurrence.next_after(date("2024-06-08")), date("2024-06-10"));
+        assert_eq!(Recurrence::Weekly(Vec::new()).next_after(date("2024-06-08")), date("2024-06-15"));
+    }
+
+    #[test]
+    fn monthly_on_a_day() {
+        let recurrence = Recurrence::Monthly(31);
+
+        assert_eq!(recurrence.next_after(date("2024-01-31")), date("2024-02-29"));
+        assert_eq!(recurrence.next_after(date("2024-02-29")), date("2024-03-31"));
+        assert_eq!(recurrence.next_after(date("2024-12-31")), date("2025-01-31"));
+        assert_eq!(Recurrence::Monthly(15).next_after(date("2024-05-10")), date("2024-05-15"));
+    }
+
+    #[test]
+    fn every_n_days() {
+        assert_eq!(Recurrence::EveryNDays(10).next_after(date("2024-02-25")), date("2024-03-06"));
+        assert_eq!(Recurrence::EveryNDays(0).next_after(date("2024-02-25")), date("2024-02-26"));
+    }
+
+    #[test]
+    fn first_due_date() {
+        let weekends = Recurrence::Weekly(vec![Weekday::Saturday, Weekday::Sunday]);
+
+        assert_eq!(weekends.first_from(date("2024-06-05")), date("2024-06-08"));
+        assert_eq!(weekends.first_from(date("2024-06-09")), date("2024-06-09"));
+        assert_eq!(Recurrence::Monthly(30).first_from(date("2023-02-28")), date("2023-02-28"));
+        assert_eq!(Recurrence::EveryNDays(3).first_from(date("2024-06-05")), date("2024-06-05"));
+    }
+
+    #[test]
+    fn describe() {
+        assert_eq!(Recurrence::Weekly(vec![Weekday::Monday, Weekday::Friday]).to_string(), "weekly on Mon, Fri");
+        assert_eq!(Recurrence::EveryNDays(3).to_string(), "every 3 days");
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::BTreeSet;
+
+use super::{ ListOptions, ToDo, ToDos };
+
+/// The rows picked for a batch action. Clicking a row's box toggles it,
+/// and shift-clicking selects every row between it and the last one
+/// clicked, like in a file manager.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Selection {
+    ids: BTreeSet<u64>,
+    /// The row the last plain click was on, where a shift-click range starts.
+    anchor: Option<u64>,
+}
+
+impl Selection {
+    pub fn contains(&self, id: u64) -> bool {
+        self.ids.contains(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// The selected ids, lowest first.
+    pub fn ids(&self) -> Vec<u64> {
+        self.ids.iter().copied().collect()
+    }
+
+    /// A click on a row's box. `order` is the rows as they're shown, which
+    /// is what a range is taken from.
+    pub fn click(&mut self, id: u64, shift: bool, order: &[u64]) {
+        let position = |id| order.iter().position(|row| *row == id);
+        let range = match (shift, self.anchor.and_then(position), position(id)) {
+            (true, Some(from), Some(to)) => Some(if from <= to { from..=to } else { to..=from }),
+            _ => None,
+        };
+
+        match range {
+            Some(range) => self.ids.extend(&order[range]),
+            None => {
+                if !self.ids.remove(&id) {
+                    self.ids.insert(id);
+                }
+                self.anchor = Some(id);
+            }
+        }
+    }
+
+    /// Whether every row in `order` is selected, and there's at least one.
+    pub fn contains_all(&self, order: &[u64]) -> bool {
+        !order.is_empty() && order.iter().all(|id| self.ids.contains(id))
+    }
+
+    /// Select every row in `order`, or clear the selection if they're all
+    /// selected already.
+    pub fn toggle_all(&mut self, order: &[u64]) {
+        if self.contains_all(order) {
+            self.clear();
+        } else {
+            self.ids.extend(order);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.anchor = None;
+    }
+
+    /// Forget rows that no longer exist, e.g. after a batch delete.
+    pub fn retain(&mut self, exists: impl Fn(u64) -> bool) {
+        self.ids.retain(|id| exists(*id));
+        self.anchor = self.anchor.filter(|id| exists(*id));
+    }
+}
+
+impl ToDos {
+    /// The ids of the rows in the list view, top to bottom: each todo that
+    /// matches `options` followed by its own rows. Sub-tasks of a collapsed
+    /// row are included, so select all picks them too.
+    pub fn shown(&self, options: &ListOptions) -> Vec<u64> {
+        self.visible(options, &BTreeSet::new())
+    }
+
+    /// The rows that can actually be seen: the same as `shown`, except the
+    /// sub-tasks of the `collapsed` rows are left out. The cursor moves
+    /// through these.
+    pub fn visible(&self, options: &ListOptions, collapsed: &BTreeSet<u64>) -> Vec<u64> {
+        let mut visible = Vec::new();
+        let mut rows: Vec<u64> = self.sorted(None, options).iter().rev().map(|todo| todo.id).collect();
+        while let Some(id) = rows.pop() {
+            visible.push(id);
+            if !collapsed.contains(&id) {
+                rows.extend(self.sorted(Some(id), options).iter().rev().map(|todo| todo.id));
+            }
+        }
+        visible
+    }
+
+    /// The todos with these ids and all of their sub-tasks, in list order.
+    /// This is what moves when they're moved to another list.
+    pub fn with_sub_tasks(&self, ids: &[u64]) -> Vec<ToDo> {
+        let mut moving: BTreeSet<u64> = ids.iter().copied().collect();
+        for id in ids {
+            moving.extend(self.descendants(*id));
+        }
+        self.0.iter().filter(|todo| moving.contains(&todo.id)).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{ Filter, Status };
+
+    const ORDER: [u64; 5] = [4, 0, 3, 1, 2];
+
+    #[test]
+    fn click_toggles_a_row() {
+        let mut selection = Selection::default();
+        selection.click(3, false, &ORDER);
+        selection.click(1, false, &ORDER);
+        selection.click(3, false, &ORDER);
+
+        assert_eq!(selection.ids(), vec![1]);
+    }
+
+    #[test]
+    fn shift_click_selects_a_range() {
+        let mut selection = Selection::default();
+        selection.click(0, false, &ORDER);
+        selection.click(1, true, &ORDER);
+        assert_eq!(selection.ids(), vec![0, 1, 3]);
+
+        // ranges go upwards too, and start from the same row
+        selection.click(4, true, &ORDER);
+        assert_eq!(selection.ids(), vec![0, 1, 3, 4]);
+
+        // without an earlier click a shift-click is a plain click
+        let mut selection = Selection::default();
+        selection.click(2, true, &ORDER);
+        assert_eq!(selection.ids(), vec![2]);
+    }
+
+    #[test]
+    fn select_all() {
+        let mut selection = Selection::default();
+        selection.click(3, false, &ORDER);
+
+        selection.toggle_all(&ORDER);
+        assert_eq!(selection.len(), 5);
+        assert!(selection.contains_all(&ORDER));
+        selection.toggle_all(&ORDER);
+        assert!(selection.is_empty());
+    }
+
+    fn todos() -> ToDos {
+        // A
+        // ├ B (done)
+        // │ └ C
+        // └ D
+        // E
+        let mut todos = ToDos(Vec::new());
+        for (title, parent) in [("A", None), ("B", Some(0)), ("C", Some(1)), ("D", Some(0)), ("E", None)] {
+            todos.new_todo(title.to_string()).unwrap().parent = parent;
+        }
+        todos.find_mut(1).unwrap().status = Status::Done;
+        todos
+    }
+
+    #[test]
+    fn rows_in_display_order() {
+        let todos = todos();
+        assert_eq!(todos.shown(&ListOptions::default()), vec![0, 1, 2, 3, 4]);
+
+        // a hidden row hides its sub-tasks too
+        let active = ListOptions { filter: Filter::Active, ..ListOptions::default() };
+        assert_eq!(todos.shown(&active), vec![0, 3, 4]);
+    }
+
+    #[test]
+    fn collapsed_rows_hide_their_sub_tasks() {
+        let todos = todos();
+        let options = ListOptions::default();
+
+        assert_eq!(todos.visible(&options, &BTreeSet::from([1])), vec![0, 1, 3, 4]);
+        assert_eq!(todos.visible(&options, &BTreeSet::from([0, 1])), vec![0, 4]);
+        // they can still be selected
+        assert_eq!(todos.shown(&options), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn move_sub_tasks_along() {
+        let todos = todos();
+        let titles = |ids: &[u64]| todos.with_sub_tasks(ids).into_iter().map(|todo| todo.title).collect::<Vec<_>>();
+
+        assert_eq!(titles(&[1, 4]), vec!["B", "C", "E"]);
+        assert_eq!(titles(&[2, 0]), vec!["A", "B", "C", "D"]);
+    }
+
+    #[test]
+    fn forget_deleted_rows() {
+        let mut selection = Selection::default();
+        selection.toggle_all(&ORDER);
+        selection.click(2, false, &ORDER);
+        selection.retain(|id| id != 3 && id != 2);
+
+        assert_eq!(selection.ids(), vec![0, 1, 4]);
+        assert_eq!(selection.anchor, None);
+    }
+}
@@ -0,0 +1,147 @@
+/// What a keyboard shortcut does in the to-do list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Down,
+    Up,
+    Toggle,
+    Edit,
+    Delete,
+    MoveUp,
+    MoveDown,
+    FocusInput,
+    Help,
+    Undo,
+    Redo,
+}
+
+impl Command {
+    /// Whether the command works on the highlighted row, which only the list
+    /// view has.
+    pub fn needs_cursor(self) -> bool {
+        matches!(
+            self,
+            Command::Down | Command::Up | Command::Toggle | Command::Edit | Command::Delete
+                | Command::MoveUp | Command::MoveDown
+        )
+    }
+}
+
+/// Every shortcut: the key as `key_name` writes it, what it does and how
+/// the cheat sheet describes it.
+pub const SHORTCUTS: [(&str, Command, &str); 11] = [
+    ("j", Command::Down, "Highlight the next to do"),
+    ("k", Command::Up, "Highlight the previous to do"),
+    ("x", Command::Toggle, "Tick or untick the highlighted to do"),
+    ("e", Command::Edit, "Edit the highlighted to do"),
+    ("d", Command::Delete, "Delete the highlighted to do"),
+    ("Alt+ArrowUp", Command::MoveUp, "Move the highlighted to do up, in the manual order"),
+    ("Alt+ArrowDown", Command::MoveDown, "Move the highlighted to do down, in the manual order"),
+    ("/", Command::FocusInput, "Type a new to do"),
+    ("?", Command::Help, "Show or hide these shortcuts"),
+    ("Ctrl+Z", Command::Undo, "Undo"),
+    ("Ctrl+Shift+Z", Command::Redo, "Redo"),
+];
+
+/// The command bound to a key, if there is one.
+pub fn command(key: &str) -> Option<Command> {
+    SHORTCUTS.iter().find(|(name, _, _)| *name == key).map(|(_, command, _)| *command)
+}
+
+/// Write a key press the way `SHORTCUTS` does, e.g. `"j"`, `"?"`,
+/// `"Ctrl+Shift+Z"` or `"Alt+ArrowUp"`. Cmd on a Mac counts as Ctrl. Shift
+/// is only written out next to Ctrl or Alt, since on its own it's already
+/// part of the key, e.g. `"?"` rather than `"Shift+/"`.
+pub fn key_name(key: &str, ctrl: bool, alt: bool, shift: bool) -> String {
+    if !ctrl && !alt {
+        return key.to_string();
+    }
+    let mut name = String::new();
+    if ctrl {
+        name += "Ctrl+";
+    }
+    if alt {
+        name += "Alt+";
+    }
+    if shift {
+        name += "Shift+";
+    }
+    // letters are written in capitals, named keys as they are
+    if key.chars().count() == 1 {
+        name + &key.to_uppercase()
+    } else {
+        name + key
+    }
+}
+
+/// The row `step` rows below the cursor (above if it's negative), stopping
+/// at the first and last rows. `rows` is the list as it's shown. Without a
+/// cursor, or if its row is no longer shown, moving down starts at the top
+/// and moving up at the bottom.
+pub fn step(rows: &[u64], cursor: Option<u64>, step: isize) -> Option<u64> {
+    let last = rows.len().checked_sub(1)?;
+    let index = match cursor.and_then(|cursor| rows.iter().position(|row| *row == cursor)) {
+        Some(index) => index.saturating_add_signed(step).min(last),
+        None if step < 0 => last,
+        None => 0,
+    };
+    Some(rows[index])
+}
+
+/// Where the cursor goes once its row is deleted: the nearest row below it
+/// that's still there, or above it if there isn't one. `before` and `after`
+/// are the rows as they were shown before and after the delete.
+pub fn nearest(before: &[u64], deleted: u64, after: &[u64]) -> Option<u64> {
+    let index = before.iter().position(|row| *row == deleted)?;
+    let remaining = |row: &&u64| after.contains(row);
+    before[index..].iter().find(remaining)
+        .or_else(|| before[..index].iter().rev().find(remaining))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn look_up_keys() {
+        assert_eq!(command(&key_name("j", false, false, false)), Some(Command::Down));
+        assert_eq!(command(&key_name("?", false, false, true)), Some(Command::Help));
+        assert_eq!(command(&key_name("z", true, false, false)), Some(Command::Undo));
+        assert_eq!(command(&key_name("Z", true, false, true)), Some(Command::Redo));
+        assert_eq!(command(&key_name("ArrowUp", false, true, false)), Some(Command::MoveUp));
+        assert_eq!(command(&key_name("ArrowDown", false, false, false)), None);
+        assert_eq!(command(&key_name("j", true, false, false)), None);
+        assert_eq!(command(&key_name("J", false, false, true)), None);
+    }
+
+    #[test]
+    fn every_key_is_bound_once() {
+        for (index, (key, _, _)) in SHORTCUTS.iter().enumerate() {
+            assert!(SHORTCUTS[index + 1..].iter().all(|(other, _, _)| other != key), "{key} is bound twice");
+        }
+    }
+
+    #[test]
+    fn move_the_cursor() {
+        let rows = [4, 0, 3];
+
+        assert_eq!(step(&rows, None, 1), Some(4));
+        assert_eq!(step(&rows, None, -1), Some(3));
+        assert_eq!(step(&rows, Some(0), 1), Some(3));
+        assert_eq!(step(&rows, Some(0), -1), Some(4));
+        // it stops at either end
+        assert_eq!(step(&rows, Some(3), 1), Some(3));
+        assert_eq!(step(&rows, Some(4), -1), Some(4));
+        // a row that's been filtered out starts it again
+        assert_eq!(step(&rows, Some(7), 1), Some(4));
+        assert_eq!(step(&[], Some(4), 1), None);
+    }
+
+    #[test]
+    fn cursor_after_a_delete() {
+        // 0 is deleted along with its sub-task 1
+        assert_eq!(nearest(&[4, 0, 1, 3], 0, &[4, 3]), Some(3));
+        assert_eq!(nearest(&[4, 0, 1], 0, &[4]), Some(4));
+        assert_eq!(nearest(&[0], 0, &[]), None);
+    }
+}
@@ -0,0 +1,279 @@
+use leptos::*;
+use leptos_router::*;
+use std::{ collections::BTreeSet, mem };
+
+use super::{ date::Date, ListsState, Priority, ToDo, ToDos };
+
+/// How many days back the chart of completed todos goes.
+const CHART_DAYS: usize = 30;
+/// The size of the chart in pixels.
+const BAR_WIDTH: usize = 12;
+const CHART_HEIGHT: usize = 100;
+
+/// How the todos are getting on: how many were completed each day lately,
+/// the current streak, how long todos take to complete and how many there
+/// are of each priority and tag. It covers every list unless one is picked
+/// in the url (`/todo/stats?list=2`).
+#[component]
+pub fn Stats() -> impl IntoView {
+
+    let ListsState { lists, .. } = expect_context();
+    let today = Date::today();
+
+    let query = use_query::<StatsQuery>();
+    let list_id = move || query.with(|query| query.as_ref().ok().and_then(|query| query.list));
+
+    // the todos of every list being summed up, all together
+    let todos = create_memo(move |_| lists.with(|lists| {
+        let list_id = list_id();
+        ToDos(lists.0.iter()
+            .filter(|list| list_id.is_none_or(|id| id == list.id))
+            .flat_map(|list| list.todos.0.iter().cloned())
+            .collect())
+    }));
+
+    let per_day = create_memo(move |_| todos.with(|todos| todos.completed_per_day(today, CHART_DAYS)));
+    let total = move || per_day.with(|days| days.iter().map(|(_, count)| count).sum::<usize>());
+
+    // a bar per day, scaled so the busiest day fills the chart
+    let bars = move || {
+        let days = per_day.get();
+        let most = days.iter().map(|(_, count)| *count).max().unwrap_or_default().max(1);
+        days.into_iter()
+            .enumerate()
+            .map(|(index, (_, count))| {
+                let height = count * CHART_HEIGHT / most;
+                view! {
+                    <rect
+                        x=index * BAR_WIDTH
+                        y=CHART_HEIGHT - height
+                        width=BAR_WIDTH - 2
+                        height=height
+                    />
+                }
+            })
+            .collect_view()
+    };
+
+    let streak = move || match todos.with(|todos| todos.streak(today)) {
+        1 => "1 day".to_string(),
+        days => format!("{days} days"),
+    };
+    let average = move || match todos.with(ToDos::average_days_to_complete) {
+        Some(days) => format!("{days:.1} days"),
+        None => "no completed todos with dates yet".to_string(),
+    };
+
+    view! {
+        <h3>"Stats"</h3>
+        <p>
+            <a href="?" class:selected=move || list_id().is_none()>"All lists"</a>
+            {move || lists.with(|lists| {
+                lists.0.iter()
+                    .map(|list| {
+                        let id = list.id;
+                        view! {
+                            " "
+                            <a href=format!("?list={id}") class:selected=move || list_id() == Some(id)>
+                                {list.name.clone()}
+                            </a>
+                        }
+                    })
+                    .collect_view()
+            })}
+        </p>
+
+        <h4>"Completed in the last " {CHART_DAYS} " days"</h4>
+        <svg
+            class="chart"
+            width=CHART_DAYS * BAR_WIDTH
+            height=CHART_HEIGHT
+            viewBox=format!("0 0 {} {CHART_HEIGHT}", CHART_DAYS * BAR_WIDTH)
+        >
+            {bars}
+        </svg>
+        <p>{total} " completed"</p>
+
+        <p>"Current streak: " {streak}</p>
+        <p>"Average time to complete: " {average}</p>
+
+        <h4>"By priority"</h4>
+        <ul>
+            {move || todos.with(ToDos::priority_counts)
+                .into_iter()
+                .map(|(priority, count)| view! { <li>{priority.label()} ": " {count}</li> })
+                .collect_view()}
+        </ul>
+
+        <h4>"By tag"</h4>
+        <ul>
+            {move || todos.with(ToDos::tag_counts)
+                .into_iter()
+                .map(|(tag, count)| view! { <li>"#" {tag} ": " {count}</li> })
+                .collect_view()}
+        </ul>
+    }
+}
+
+/// The query string of the `/todo/stats` route.
+#[derive(Params, Debug, Clone, PartialEq)]
+struct StatsQuery {
+    list: Option<u64>,
+}
+
+impl ToDos {
+    /// How many todos were completed on each of the `days` days up to and
+    /// including `today`, oldest first. A recurring todo counts each time
+    /// it's completed.
+    pub fn completed_per_day(&self, today: Date, days: usize) -> Vec<(Date, usize)> {
+        (0..days as i64)
+            .rev()
+            .map(|ago| today.add_days(-ago))
+            .map(|day| {
+                let count = self.0.iter()
+                    .flat_map(ToDo::completion_days)
+                    .filter(|completed| *completed == day)
+                    .count();
+                (day, count)
+            })
+            .collect()
+    }
+
+    /// How many days in a row todos have been completed, counting back from
+    /// today. Nothing completed yet today doesn't break the streak, since
+    /// the day isn't over.
+    pub fn streak(&self, today: Date) -> usize {
+        let days: BTreeSet<Date> = self.0.iter().flat_map(ToDo::completion_days).collect();
+        let last = if days.contains(&today) { today } else { today.add_days(-1) };
+        (0_i64..).take_while(|ago| days.contains(&last.add_days(-ago))).count()
+    }
+
+    /// The average number of days between adding a todo and completing it.
+    /// Each time a recurring todo is completed counts from the time before,
+    /// or from when it was added the first time. Todos saved before creation
+    /// dates were kept are left out.
+    pub fn average_days_to_complete(&self) -> Option<f64> {
+        let days: Vec<i64> = self.0.iter()
+            .filter_map(|todo| Some((todo.created_on?, todo)))
+            .flat_map(|(created_on, todo)| {
+                let mut since = created_on;
+                todo.completion_days().map(move |day| day.days_since(mem::replace(&mut since, day)))
+            })
+            .collect();
+        (!days.is_empty()).then(|| days.iter().sum::<i64>() as f64 / days.len() as f64)
+    }
+
+    /// How many todos have each priority, from lowest to highest.
+    pub fn priority_counts(&self) -> Vec<(Priority, usize)> {
+        Priority::ALL.into_iter()
+            .map(|priority| (priority, self.0.iter().filter(|todo| todo.priority == priority).count()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::recurrence::Recurrence;
+
+    fn date(date: &str) -> Date {
+        date.parse().unwrap()
+    }
+
+    /// A todo added on `created` and completed on `completed`, if it was.
+    fn todo(created: &str, completed: Option<&str>) -> ToDo {
+        let mut todo = ToDo { created_on: Some(date(created)), ..ToDo::new(created.to_string()) };
+        if let Some(completed) = completed {
+            todo.toggle(date(completed));
+        }
+        todo
+    }
+
+    #[test]
+    fn completed_per_day() {
+        let todos = ToDos(vec![
+            todo("2024-06-01", Some("2024-06-03")),
+            todo("2024-06-01", Some("2024-06-03")),
+            todo("2024-06-01", Some("2024-06-01")),
+            todo("2024-05-01", Some("2024-05-01")),
+            todo("2024-06-02", None),
+        ]);
+
+        assert_eq!(todos.completed_per_day(date("2024-06-03"), 3), vec![
+            (date("2024-06-01"), 1),
+            (date("2024-06-02"), 0),
+            (date("2024-06-03"), 2),
+        ]);
+    }
+
+    #[test]
+    fn streak() {
+        let todos = ToDos(vec![
+            todo("2024-06-01", Some("2024-06-01")),
+            todo("2024-06-01", Some("2024-06-02")),
+            todo("2024-06-01", Some("2024-06-03")),
+            todo("2024-06-01", Some("2024-06-05")),
+        ]);
+
+        assert_eq!(todos.streak(date("2024-06-03")), 3);
+        // the streak carries on until a whole day goes by without anything
+        assert_eq!(todos.streak(date("2024-06-04")), 3);
+        assert_eq!(todos.streak(date("2024-06-05")), 1);
+        assert_eq!(todos.streak(date("2024-06-07")), 0);
+    }
+
+    #[test]
+    fn average_days_to_complete() {
+        let mut todos = ToDos(vec![
+            todo("2024-06-01", Some("2024-06-01")),
+            todo("2024-05-30", Some("2024-06-02")),
+            todo("2024-05-01", None),
+        ]);
+        assert_eq!(todos.average_days_to_complete(), Some(1.5));
+
+        // completed todos without a creation date are left out
+        todos.0.push(ToDo { created_on: None, ..todo("2024-01-01", Some("2024-06-01")) });
+        assert_eq!(todos.average_days_to_complete(), Some(1.5));
+        assert_eq!(ToDos(Vec::new()).average_days_to_complete(), None);
+    }
+
+    #[test]
+    fn recurring_completions() {
+        // a daily habit done three days running. It's never left done, so
+        // only its completions count.
+        let mut habit = ToDo {
+            due: Some(date("2024-06-01")),
+            recurrence: Some(Recurrence::Daily),
+            ..todo("2024-05-31", None)
+        };
+        for day in ["2024-06-01", "2024-06-02", "2024-06-03"] {
+            habit.toggle(date(day));
+        }
+        assert!(!habit.is_completed());
+        let todos = ToDos(vec![habit, todo("2024-06-01", Some("2024-06-03"))]);
+
+        assert_eq!(todos.completed_per_day(date("2024-06-03"), 3), vec![
+            (date("2024-06-01"), 1),
+            (date("2024-06-02"), 1),
+            (date("2024-06-03"), 2),
+        ]);
+        assert_eq!(todos.streak(date("2024-06-03")), 3);
+        // 1 day after it was added, then a day each time, and 2 days for the
+        // other todo
+        assert_eq!(todos.average_days_to_complete(), Some(1.25));
+    }
+
+    #[test]
+    fn priority_counts() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("A".to_string()).unwrap().priority = Priority::High;
+        todos.new_todo("B".to_string()).unwrap();
+        todos.new_todo("C".to_string()).unwrap();
+
+        assert_eq!(todos.priority_counts(), vec![
+            (Priority::Low, 0),
+            (Priority::Normal, 2),
+            (Priority::High, 1),
+        ]);
+    }
+}
@@ -0,0 +1,291 @@
+use leptos::window;
+use serde_json::{ json, Value };
+use std::fmt;
+
+use super::lists::Lists;
+
+/// The localStorage key the to-do lists are saved under.
+const STORAGE_KEY: &str = "leptos-tutorial.todos";
+
+/// The version of the layout written by `save`. Bump this whenever the
+/// shape of `ToDo` or `ToDoList` changes and add a matching function to
+/// `MIGRATIONS`.
+const SCHEMA_VERSION: u64 = 11;
+
+/// Each migration upgrades a saved list by exactly one version, so
+/// `MIGRATIONS[0]` turns version 1 into version 2 and so on. Lists saved by
+/// older versions of the app are walked up the chain until they match
+/// `SCHEMA_VERSION`.
+const MIGRATIONS: &[fn(Value) -> Value] = &[
+    v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7, v7_to_v8, v8_to_v9, v9_to_v10,
+    v10_to_v11,
+];
+
+/// Reasons a saved list could not be read back.
+#[derive(Debug, PartialEq)]
+pub enum StorageError {
+    /// The saved text wasn't valid JSON or didn't match the schema.
+    Malformed(String),
+    /// The list was saved by a newer version of the app than this one.
+    UnknownVersion(u64),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Malformed(reason) => write!(f, "saved to-dos are malformed: {reason}"),
+            StorageError::UnknownVersion(version) => {
+                write!(f, "saved to-dos use unknown schema version {version}")
+            }
+        }
+    }
+}
+
+/// Read the saved lists from localStorage. Anything that can't be read
+/// is logged and replaced with a single empty list so the app still starts.
+pub fn load() -> Lists {
+    let raw = local_storage().and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten());
+
+    match raw.map(|raw| decode(&raw)) {
+        Some(Ok(lists)) => lists,
+        Some(Err(err)) => {
+            log::warn!("{err}");
+            Lists::default()
+        }
+        None => Lists::default(),
+    }
+}
+
+/// Write the lists to localStorage, overwriting whatever was there.
+pub fn save(lists: &Lists) {
+    let Some(storage) = local_storage() else { return };
+
+    if storage.set_item(STORAGE_KEY, &encode(lists)).is_err() {
+        log::warn!("could not save to-dos to localStorage");
+    }
+}
+
+pub fn local_storage() -> Option<web_sys::Storage> {
+    window().local_storage().ok().flatten()
+}
+
+/// Wrap the lists in an envelope that records which schema they were written with.
+pub fn encode(lists: &Lists) -> String {
+    json!({ "version": SCHEMA_VERSION, "lists": lists }).to_string()
+}
+
+/// Parse a saved envelope, migrating it up to `SCHEMA_VERSION` first.
+pub fn decode(raw: &str) -> Result<Lists, StorageError> {
+    let malformed = |err: serde_json::Error| StorageError::Malformed(err.to_string());
+
+    let mut envelope: Value = serde_json::from_str(raw).map_err(malformed)?;
+    let version = envelope["version"]
+        .as_u64()
+        .ok_or_else(|| StorageError::Malformed("missing version".to_string()))?;
+
+    if version == 0 || version > SCHEMA_VERSION {
+        return Err(StorageError::UnknownVersion(version));
+    }
+
+    // before version 7 there was only one list, saved under `todos`
+    let key = if version < 7 { "todos" } else { "lists" };
+    let mut saved = envelope[key].take();
+    for migrate in &MIGRATIONS[(version - 1) as usize..] {
+        saved = migrate(saved);
+    }
+
+    serde_json::from_value(saved).map_err(malformed)
+}
+
+/// Version 2 gave every todo an id. Number the saved todos in order.
+fn v1_to_v2(mut todos: Value) -> Value {
+    if let Some(todos) = todos.as_array_mut() {
+        for (id, todo) in todos.iter_mut().enumerate() {
+            todo["id"] = json!(id);
+        }
+    }
+    todos
+}
+
+/// Version 3 added due dates and priorities.
+fn v2_to_v3(mut todos: Value) -> Value {
+    if let Some(todos) = todos.as_array_mut() {
+        for todo in todos {
+            todo["due"] = Value::Null;
+            todo["priority"] = json!("normal");
+        }
+    }
+    todos
+}
+
+/// Version 4 added tags.
+fn v3_to_v4(mut todos: Value) -> Value {
+    if let Some(todos) = todos.as_array_mut() {
+        for todo in todos {
+            todo["tags"] = json!([]);
+        }
+    }
+    todos
+}
+
+/// Version 5 added sub-tasks.
+fn v4_to_v5(mut todos: Value) -> Value {
+    if let Some(todos) = todos.as_array_mut() {
+        for todo in todos {
+            todo["parent"] = Value::Null;
+        }
+    }
+    todos
+}
+
+/// Version 6 added recurring todos.
+fn v5_to_v6(mut todos: Value) -> Value {
+    if let Some(todos) = todos.as_array_mut() {
+        for todo in todos {
+            todo["recurrence"] = Value::Null;
+        }
+    }
+    todos
+}
+
+/// Version 7 added named lists. The todos saved so far become the first one.
+fn v6_to_v7(todos: Value) -> Value {
+    json!([{ "id": 0, "name": "To Do", "todos": todos }])
+}
+
+/// Version 8 replaced `completed` with a status. Completed todos are done
+/// and the rest go in the backlog.
+fn v7_to_v8(mut lists: Value) -> Value {
+    if let Some(lists) = lists.as_array_mut() {
+        for list in lists {
+            if let Some(todos) = list["todos"].as_array_mut() {
+                for todo in todos {
+                    let completed = todo["completed"].as_bool().unwrap_or(false);
+                    todo["status"] = json!(if completed { "done" } else { "backlog" });
+                    if let Some(todo) = todo.as_object_mut() {
+                        todo.remove("completed");
+                    }
+                }
+            }
+        }
+    }
+    lists
+}
+
+/// Version 9 added the time spent on each todo with the pomodoro timer.
+fn v8_to_v9(mut lists: Value) -> Value {
+    if let Some(lists) = lists.as_array_mut() {
+        for list in lists {
+            if let Some(todos) = list["todos"].as_array_mut() {
+                for todo in todos {
+                    todo["focus_seconds"] = json!(0);
+                }
+            }
+        }
+    }
+    lists
+}
+
+/// Version 10 added the days todos were created and completed. They're
+/// unknown for the todos saved before.
+fn v9_to_v10(mut lists: Value) -> Value {
+    if let Some(lists) = lists.as_array_mut() {
+        for list in lists {
+            if let Some(todos) = list["todos"].as_array_mut() {
+                for todo in todos {
+                    todo["created_on"] = Value::Null;
+                    todo["completed_on"] = Value::Null;
+                }
+            }
+        }
+    }
+    lists
+}
+
+/// Version 11 kept the days recurring todos were completed. Those before
+/// weren't recorded.
+fn v10_to_v11(mut lists: Value) -> Value {
+    if let Some(lists) = lists.as_array_mut() {
+        for list in lists {
+            if let Some(todos) = list["todos"].as_array_mut() {
+                for todo in todos {
+                    todo["completions"] = json!([]);
+                }
+            }
+        }
+    }
+    lists
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Status;
+
+    #[test]
+    fn every_old_version_has_a_migration() {
+        assert_eq!(MIGRATIONS.len() as u64, SCHEMA_VERSION - 1);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut lists = Lists::default();
+        let id = 1;
+        lists.new_list(id, "Work".to_string()).unwrap();
+        let todos = &mut lists.find_mut(id).unwrap().todos;
+        todos.new_todo("Task 1".to_string()).unwrap();
+        todos.new_todo("Task 2".to_string()).unwrap();
+        todos.0[1].toggle("2024-02-28".parse().unwrap());
+        todos.0[1].due = "2024-02-29".parse().ok();
+
+        let decoded = decode(&encode(&lists)).unwrap();
+        assert_eq!(decoded.0.len(), 2);
+        assert_eq!(decoded.find(id).unwrap().name, "Work");
+        assert_eq!(decoded.find(id).unwrap().todos.get(), lists.find(id).unwrap().todos.get());
+    }
+
+    #[test]
+    fn decode_version_1() {
+        let raw = r#"{"version":1,"todos":[{"title":"Task","completed":true}]}"#;
+        let lists = decode(raw).unwrap();
+        let todos = &lists.find(0).unwrap().todos;
+
+        assert_eq!(lists.0.len(), 1);
+        assert_eq!(todos.num_remaining(), 0);
+        assert_eq!(todos.get()[0].title, "Task");
+    }
+
+    #[test]
+    fn migrate_version_1_ids() {
+        let raw = r#"{"version":1,"todos":[{"title":"A","completed":false},{"title":"B","completed":false}]}"#;
+        let ids: Vec<u64> = decode(raw).unwrap().0[0].todos.get().iter().map(|todo| todo.id).collect();
+
+        assert_eq!(ids, vec![0, 1]);
+    }
+
+    #[test]
+    fn migrate_completed_to_done() {
+        let raw = r#"{"version":7,"lists":[{"id":3,"name":"Home","todos":[
+            {"id":0,"title":"A","completed":true,"due":null,"priority":"normal","tags":[],"parent":null,"recurrence":null},
+            {"id":1,"title":"B","completed":false,"due":null,"priority":"normal","tags":[],"parent":null,"recurrence":null}
+        ]}]}"#;
+        let lists = decode(raw).unwrap();
+        let statuses: Vec<Status> = lists.find(3).unwrap().todos.get().iter().map(|todo| todo.status).collect();
+
+        assert_eq!(statuses, vec![Status::Done, Status::Backlog]);
+    }
+
+    #[test]
+    fn reject_newer_version() {
+        let raw = format!(r#"{{"version":{},"todos":[]}}"#, SCHEMA_VERSION + 1);
+
+        assert_eq!(decode(&raw).err(), Some(StorageError::UnknownVersion(SCHEMA_VERSION + 1)));
+    }
+
+    #[test]
+    fn reject_malformed() {
+        assert!(matches!(decode("not json"), Err(StorageError::Malformed(_))));
+        assert!(matches!(decode(r#"{"todos":[]}"#), Err(StorageError::Malformed(_))));
+        assert!(matches!(decode(r#"{"version":7,"todos":[]}"#), Err(StorageError::Malformed(_))));
+    }
+}
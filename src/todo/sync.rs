@@ -0,0 +1,662 @@
+use gloo_net::http::{ Request, Response };
+use gloo_timers::callback::Interval;
+use leptos::*;
+use serde::{ Deserialize, Serialize };
+use std::{ collections::{ BTreeMap, BTreeSet }, fmt };
+
+use crate::retry::is_retryable;
+use super::{ lists::Lists, storage, ListsState, ToDo };
+
+// Keeps the lists in step with the server in `server/`. Every change is
+// put in a queue, the outbox, which is sent to the server a todo at a time.
+// While the server can't be reached the queue just grows, and it's saved to
+// localStorage so edits made offline survive a reload. Once the outbox is
+// empty the changes other clients made are pulled down.
+//
+// The server numbers every write with a revision. A change is sent along
+// with the revision it was made to, so the server can tell when another
+// client changed the same todo in the meantime. Then the change that was
+// made last wins, and the client that lost takes the winning todo.
+//
+// Only todos are synced, not the lists themselves. Changes to lists this
+// client doesn't have are skipped, and once it gets a new list everything
+// is pulled again from the start so the todos skipped for it arrive.
+//
+// Todos are keyed on the server by their list's id and their own, so two
+// clients must never make the same id for different things. New todos and
+// lists get their ids from `new_id` instead of counting up from the
+// highest one, which every client would do the same way.
+
+/// Where `server/` listens by default.
+const SERVER_URL: &str = "http://127.0.0.1:3000";
+
+/// How often the outbox is sent and other clients' changes pulled.
+const SYNC_INTERVAL_MS: u32 = 5_000;
+
+/// The localStorage key the outbox and server revisions are saved under.
+const STORAGE_KEY: &str = "leptos-tutorial.sync";
+
+/// How many of the low bits of an id are random.
+const RANDOM_BITS: u32 = 20;
+
+/// An id for a new todo or list: the time in milliseconds with random bits
+/// below it. Ids made later are still bigger, so sorting by id still sorts
+/// by when things were made, and one deleted here is never made again.
+pub fn new_id() -> u64 {
+    unique_id(js_sys::Date::now() as u64, rand::random())
+}
+
+fn unique_id(now: u64, random: u64) -> u64 {
+    now << RANDOM_BITS | random & ((1 << RANDOM_BITS) - 1)
+}
+
+/// Whether the lists are in step with the server, next to the list of
+/// lists. Syncing runs for as long as this is mounted.
+#[component]
+pub fn SyncStatus() -> impl IntoView {
+
+    let ListsState { lists, set_lists } = expect_context();
+
+    let state = store_value(load());
+    let (pending, set_pending) = create_signal(0);
+    // `None` until the server has been tried once
+    let (online, set_online) = create_signal(None::<bool>);
+
+    // queue whatever changed after every change to the lists. Changes that
+    // came from the server match its copy, so they aren't queued again.
+    create_effect(move |_| {
+        let now = js_sys::Date::now() as u64;
+        lists.with(|lists| state.update_value(|state| state.record(lists, now)));
+        state.with_value(save);
+        set_pending.set(state.with_value(SyncState::pending));
+    });
+
+    // only one sync runs at a time. A slow server could otherwise have the
+    // same change sent twice.
+    let busy = store_value(false);
+    let sync = move || {
+        if busy.get_value() {
+            return;
+        }
+        busy.set_value(true);
+        spawn_local(async move {
+            let result = flush(state, set_lists).await;
+            if let Err(err) = &result {
+                log::debug!("sync failed: {err}");
+            }
+            // the lists may have been closed while waiting for the server
+            _ = set_online.try_set(Some(result.is_ok()));
+            _ = busy.try_set_value(false);
+        });
+    };
+
+    sync();
+    let interval = Interval::new(SYNC_INTERVAL_MS, sync);
+    on_cleanup(move || drop(interval));
+    // don't wait for the next tick when the browser gets its connection back
+    let handle = window_event_listener(ev::online, move |_| sync());
+    on_cleanup(move || handle.remove());
+
+    let status = move || match (online.get(), pending.get()) {
+        (None, _) => "Connecting to the server…".to_string(),
+        (Some(true), 0) => "Synced".to_string(),
+        (Some(true), count) => format!("Syncing {count} changes…"),
+        (Some(false), count) => format!("Offline, {count} changes waiting"),
+    };
+
+    view! {
+        <p>
+            <small>{status}</small>
+            " "
+            <button on:click=move |_| sync()>"Sync now"</button>
+        </p>
+    }
+}
+
+/// Send everything in the outbox, oldest first, then pull the changes
+/// other clients made. Stops at the first request that fails, leaving the
+/// rest queued, unless the server turned the change down for good. Then
+/// it's put aside so it doesn't hold up the changes behind it.
+async fn flush(state: StoredValue<SyncState>, set_lists: WriteSignal<Lists>) -> Result<(), SyncError> {
+    while let Some((mutation, base_revision)) = state.try_with_value(SyncState::next).flatten() {
+        match send(&mutation, base_revision).await {
+            Ok(result) => {
+                set_lists.try_update(|lists| state.update_value(|state| state.sent(lists, &mutation, result)));
+            }
+            Err(SyncError::Status(status)) if !is_retryable(status) => {
+                log::warn!("the server rejected a change to todo {} with {status}", mutation.id);
+                state.try_update_value(|state| state.rejected(&mutation));
+                state.try_with_value(save);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    let Some(since) = state.try_with_value(|state| state.pulled) else { return Ok(()) };
+    let changes = pull(since).await?;
+    set_lists.try_update(|lists| state.update_value(|state| state.pulled(lists, changes)));
+    Ok(())
+}
+
+async fn send(mutation: &Mutation, base_revision: u64) -> Result<WriteResult, SyncError> {
+    let (path, write) = write_request(mutation, base_revision);
+    let url = format!("{SERVER_URL}{path}");
+    let request = match write {
+        Some(write) => Request::put(&url).json(&write)?,
+        None => Request::delete(&url).build()?,
+    };
+    read_json(request.send().await?).await
+}
+
+async fn pull(since: u64) -> Result<Changes, SyncError> {
+    read_json(Request::get(&format!("{SERVER_URL}{}", pull_path(since))).send().await?).await
+}
+
+/// Where a change is sent, and the body of a `PUT` that creates or changes
+/// a todo. A delete has no body: it's a `DELETE` with the revision and time
+/// in the query.
+fn write_request(mutation: &Mutation, base_revision: u64) -> (String, Option<Write<'_>>) {
+    let path = format!("/lists/{}/todos/{}", mutation.list_id, mutation.id);
+    match &mutation.todo {
+        Some(todo) => (path, Some(Write { base_revision, updated_at: mutation.updated_at, todo })),
+        None => (format!("{path}?base_revision={base_revision}&updated_at={}", mutation.updated_at), None),
+    }
+}
+
+fn pull_path(since: u64) -> String {
+    format!("/todos?since={since}")
+}
+
+/// The body of a successful response. Error statuses count as failures so
+/// the change stays queued.
+async fn read_json<T: for<'de> Deserialize<'de>>(response: Response) -> Result<T, SyncError> {
+    if !response.ok() {
+        return Err(SyncError::Status(response.status()));
+    }
+    Ok(response.json().await?)
+}
+
+/// Read the saved sync state. If there's none, or it can't be read,
+/// syncing starts from scratch and every todo is sent again.
+fn load() -> SyncState {
+    storage::local_storage()
+        .and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save(state: &SyncState) {
+    let Some(storage) = storage::local_storage() else { return };
+    let saved = serde_json::to_string(state).is_ok_and(|raw| storage.set_item(STORAGE_KEY, &raw).is_ok());
+    if !saved {
+        log::warn!("could not save the sync outbox to localStorage");
+    }
+}
+
+/// A change to one todo that hasn't reached the server yet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mutation {
+    pub list_id: u64,
+    pub id: u64,
+    /// The todo as it is now, or `None` if it was deleted.
+    pub todo: Option<ToDo>,
+    /// When the change was made, in milliseconds since 1970.
+    pub updated_at: u64,
+}
+
+impl Mutation {
+    /// Whether this is the change that makes a todo `todo`.
+    fn is(&self, list_id: u64, id: u64, todo: &Option<ToDo>) -> bool {
+        self.list_id == list_id && self.id == id && self.todo == *todo
+    }
+}
+
+/// A todo as the server has it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub list_id: u64,
+    pub id: u64,
+    pub revision: u64,
+    pub updated_at: u64,
+    /// `None` if it was deleted.
+    pub todo: Option<ToDo>,
+}
+
+/// What's sent to create or change a todo.
+#[derive(Serialize)]
+struct Write<'a> {
+    base_revision: u64,
+    updated_at: u64,
+    todo: &'a ToDo,
+}
+
+/// The server's answer to a change.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WriteResult {
+    /// False if another client changed the todo later.
+    pub applied: bool,
+    /// The todo as it is now, whichever change won.
+    pub record: Record,
+}
+
+/// Every todo written since some revision.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Changes {
+    pub revision: u64,
+    pub records: Vec<Record>,
+}
+
+/// Why talking to the server failed.
+#[derive(Debug)]
+enum SyncError {
+    /// The server couldn't be reached, or its answer couldn't be read.
+    Network(gloo_net::Error),
+    /// The server answered with an error status.
+    Status(u16),
+}
+
+impl From<gloo_net::Error> for SyncError {
+    fn from(err: gloo_net::Error) -> Self {
+        SyncError::Network(err)
+    }
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::Network(err) => write!(f, "{err}"),
+            SyncError::Status(status) => write!(f, "the server replied {status}"),
+        }
+    }
+}
+
+/// Everything this client needs to remember between syncs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncState {
+    /// Changes waiting to be sent, oldest first. There's at most one per
+    /// todo.
+    outbox: Vec<Mutation>,
+    /// The server's copy of each todo as of the last time this client heard
+    /// about it, by list id and then todo id.
+    server: BTreeMap<u64, BTreeMap<u64, Record>>,
+    /// The revision the last pull got up to.
+    pulled: u64,
+    /// The ids of the lists as of the last `record`. A pull skips todos of
+    /// lists missing from here.
+    #[serde(default)]
+    lists: BTreeSet<u64>,
+    /// Changes the server turned down for good. They stay out of the outbox
+    /// until the todo is changed again.
+    #[serde(default)]
+    rejected: Vec<Mutation>,
+}
+
+impl SyncState {
+    /// How many changes are waiting to be sent.
+    pub fn pending(&self) -> usize {
+        self.outbox.len()
+    }
+
+    /// Bring the outbox up to date with the lists. Every todo that's
+    /// different from the server's copy gets a change, and so does every
+    /// todo the server has that the lists don't. A change that's already
+    /// queued keeps its place and time unless the todo changed again. If
+    /// there's a list that wasn't there before, the next pull starts over.
+    pub fn record(&mut self, lists: &Lists, now: u64) {
+        let ids: BTreeSet<u64> = lists.0.iter().map(|list| list.id).collect();
+        if !ids.is_subset(&self.lists) {
+            self.pulled = 0;
+        }
+        self.lists = ids;
+
+        let mut wanted = Vec::new();
+        for list in &lists.0 {
+            for todo in &list.todos.0 {
+                if self.server_copy(list.id, todo.id) != Some(todo) {
+                    wanted.push((list.id, todo.id, Some(todo.clone())));
+                }
+            }
+        }
+        for (list_id, records) in &self.server {
+            for (id, record) in records {
+                let local = lists.find(*list_id).and_then(|list| list.todos.find(*id));
+                if record.todo.is_some() && local.is_none() {
+                    wanted.push((*list_id, *id, None));
+                }
+            }
+        }
+
+        self.rejected.retain(|rejected| wanted.iter().any(|(list_id, id, todo)| rejected.is(*list_id, *id, todo)));
+        let mut outbox: Vec<Mutation> = wanted.into_iter()
+            .filter(|(list_id, id, todo)| !self.rejected.iter().any(|rejected| rejected.is(*list_id, *id, todo)))
+            .map(|(list_id, id, todo)| {
+                let queued = self.outbox.iter().find(|mutation| mutation.is(list_id, id, &todo));
+                queued.cloned().unwrap_or(Mutation { list_id, id, todo, updated_at: now })
+            })
+            .collect();
+        outbox.sort_by_key(|mutation| mutation.updated_at);
+        self.outbox = outbox;
+    }
+
+    /// The oldest change in the outbox and the revision of the todo it was
+    /// made to, 0 if the server has never had it.
+    pub fn next(&self) -> Option<(Mutation, u64)> {
+        let mutation = self.outbox.first()?.clone();
+        let revision = self.server.get(&mutation.list_id)
+            .and_then(|records| records.get(&mutation.id))
+            .map_or(0, |record| record.revision);
+        Some((mutation, revision))
+    }
+
+    /// The server answered a change. If the change lost to another client's,
+    /// the lists take the winning todo, unless it has been changed yet
+    /// again in the meantime.
+    pub fn sent(&mut self, lists: &mut Lists, mutation: &Mutation, result: WriteResult) {
+        self.outbox.retain(|queued| queued != mutation);
+        let record = result.record;
+        if !result.applied && !self.is_queued(record.list_id, record.id) {
+            adopt(lists, &record);
+        }
+        self.server.entry(record.list_id).or_default().insert(record.id, record);
+    }
+
+    /// The server turned a change down with a status that sending it again
+    /// wouldn't change. It's put aside until the todo changes again.
+    pub fn rejected(&mut self, mutation: &Mutation) {
+        self.outbox.retain(|queued| queued != mutation);
+        self.rejected.push(mutation.clone());
+    }
+
+    /// Take the changes other clients made. Todos with a change of their own
+    /// still queued are left alone: sending it sorts out which one wins.
+    pub fn pulled(&mut self, lists: &mut Lists, changes: Changes) {
+        for record in changes.records {
+            if self.is_queued(record.list_id, record.id) || lists.find(record.list_id).is_none() {
+                continue;
+            }
+            adopt(lists, &record);
+            self.server.entry(record.list_id).or_default().insert(record.id, record);
+        }
+        self.pulled = changes.revision;
+    }
+
+    fn server_copy(&self, list_id: u64, id: u64) -> Option<&ToDo> {
+        self.server.get(&list_id)?.get(&id)?.todo.as_ref()
+    }
+
+    fn is_queued(&self, list_id: u64, id: u64) -> bool {
+        self.outbox.iter().any(|mutation| mutation.list_id == list_id && mutation.id == id)
+    }
+}
+
+/// Make a list's copy of a todo match the server's.
+fn adopt(lists: &mut Lists, record: &Record) {
+    let Some(list) = lists.find_mut(record.list_id) else { return };
+    match &record.todo {
+        Some(todo) if list.todos.find(record.id).is_some() => list.todos.replace(todo.clone()),
+        Some(todo) => list.todos.0.push(todo.clone()),
+        None => { list.todos.remove_where(|todo| todo.id == record.id); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{ list_todos, ToDoAction };
+    use axum::{ body::{ to_bytes, Body }, http, Router };
+    use todo_server::{ routes, store::Store };
+    use tower::ServiceExt;
+
+    /// One list with a todo for each title, numbered from 0.
+    fn lists(titles: &[&str]) -> Lists {
+        let mut lists = Lists::default();
+        for title in titles {
+            lists.find_mut(0).unwrap().todos.new_todo(title.to_string()).unwrap();
+        }
+        lists
+    }
+
+    fn titles(lists: &Lists) -> Vec<String> {
+        lists.find(0).unwrap().todos.0.iter().map(|todo| todo.title.clone()).collect()
+    }
+
+    /// A server from `server/` with no todos yet, run in this process.
+    fn server() -> Router {
+        routes::app(Store::default())
+    }
+
+    /// Send one request to `server` and read its answer.
+    async fn request<T: for<'de> Deserialize<'de>>(server: &Router, method: &str, path: String, body: Option<String>) -> T {
+        let request = http::Request::builder()
+            .method(method)
+            .uri(path)
+            .header("content-type", "application/json")
+            .body(body.map_or_else(Body::empty, Body::from))
+            .unwrap();
+        let response = server.clone().oneshot(request).await.unwrap();
+        assert!(response.status().is_success(), "the server replied {}", response.status());
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    /// What `flush` does, against `server`.
+    async fn sync(state: &mut SyncState, lists: &mut Lists, server: &Router) {
+        while let Some((mutation, base_revision)) = state.next() {
+            let (path, write) = write_request(&mutation, base_revision);
+            let result = match write {
+                Some(write) => request(server, "PUT", path, Some(serde_json::to_string(&write).unwrap())).await,
+                None => request(server, "DELETE", path, None).await,
+            };
+            state.sent(lists, &mutation, result);
+        }
+        let changes = request(server, "GET", pull_path(state.pulled), None).await;
+        state.pulled(lists, changes);
+    }
+
+    #[test]
+    fn new_ids_go_up() {
+        assert!(unique_id(1_001, 0) > unique_id(1_000, u64::MAX));
+        assert_ne!(unique_id(1_000, 1), unique_id(1_000, 2));
+    }
+
+    #[tokio::test]
+    async fn two_clients_add_todos_at_once() {
+        let server = server();
+        let (mut phone, mut phone_state) = (Lists::default(), SyncState::default());
+        let (mut laptop, mut laptop_state) = (Lists::default(), SyncState::default());
+
+        // both are offline and add a todo in the same millisecond
+        let add = |lists: &mut Lists, random: u64, title: &str| {
+            let todo = ToDo { id: unique_id(1_000, random), ..ToDo::new(title.to_string()) };
+            lists.find_mut(0).unwrap().todos.reduce(&ToDoAction::Add(todo)).unwrap();
+        };
+        add(&mut phone, 1, "Milk");
+        add(&mut laptop, 2, "Report");
+        phone_state.record(&phone, 100);
+        laptop_state.record(&laptop, 100);
+
+        sync(&mut phone_state, &mut phone, &server).await;
+        sync(&mut laptop_state, &mut laptop, &server).await;
+        sync(&mut phone_state, &mut phone, &server).await;
+
+        // neither todo overwrote the other
+        assert_eq!(titles(&phone), vec!["Milk", "Report"]);
+        assert_eq!(titles(&laptop), vec!["Report", "Milk"]);
+        assert_eq!(request::<Changes>(&server, "GET", pull_path(0), None).await.records.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn queue_changes_until_sent() {
+        let mut lists = lists(&["A", "B"]);
+        let mut state = SyncState::default();
+        let server = server();
+
+        state.record(&lists, 100);
+        assert_eq!(state.pending(), 2);
+        sync(&mut state, &mut lists, &server).await;
+        assert_eq!(state.pending(), 0);
+
+        // an edit and a delete while offline
+        lists.find_mut(0).unwrap().todos.edit(0, "A2".to_string()).unwrap();
+        state.record(&lists, 200);
+        lists.find_mut(0).unwrap().todos.delete(1);
+        state.record(&lists, 300);
+
+        let queued: Vec<(u64, Option<String>, u64)> = state.outbox.iter()
+            .map(|mutation| (mutation.id, mutation.todo.as_ref().map(|todo| todo.title.clone()), mutation.updated_at))
+            .collect();
+        assert_eq!(queued, vec![(0, Some("A2".to_string()), 200), (1, None, 300)]);
+        assert_eq!(state.next().map(|(_, revision)| revision), Some(1));
+    }
+
+    #[tokio::test]
+    async fn undoing_a_change_unqueues_it() {
+        let mut lists = lists(&["A"]);
+        let mut state = SyncState::default();
+        let server = server();
+        state.record(&lists, 100);
+        sync(&mut state, &mut lists, &server).await;
+
+        lists.find_mut(0).unwrap().todos.edit(0, "B".to_string()).unwrap();
+        state.record(&lists, 200);
+        lists.find_mut(0).unwrap().todos.edit(0, "A".to_string()).unwrap();
+        state.record(&lists, 300);
+
+        assert_eq!(state.pending(), 0);
+    }
+
+    #[test]
+    fn put_a_rejected_change_aside() {
+        let mut lists = lists(&["A", "B"]);
+        let mut state = SyncState::default();
+        state.record(&lists, 100);
+
+        let (rejected, _) = state.next().unwrap();
+        state.rejected(&rejected);
+        assert_eq!(state.next().map(|(mutation, _)| mutation.id), Some(1));
+        // it isn't queued again while the todo stays the same
+        state.record(&lists, 200);
+        assert_eq!(state.pending(), 1);
+
+        // but a new change to the todo is
+        lists.find_mut(0).unwrap().todos.edit(0, "A2".to_string()).unwrap();
+        state.record(&lists, 300);
+        assert_eq!(state.pending(), 2);
+    }
+
+    #[tokio::test]
+    async fn take_the_winner_of_a_conflict() {
+        let server = server();
+        let (mut phone, mut phone_state) = (lists(&["Shared"]), SyncState::default());
+        phone_state.record(&phone, 100);
+        sync(&mut phone_state, &mut phone, &server).await;
+        let (mut laptop, mut laptop_state) = (Lists::default(), SyncState::default());
+        sync(&mut laptop_state, &mut laptop, &server).await;
+
+        // both edit the todo, the laptop later
+        phone.find_mut(0).unwrap().todos.edit(0, "Mine".to_string()).unwrap();
+        phone_state.record(&phone, 120);
+        laptop.find_mut(0).unwrap().todos.edit(0, "Theirs".to_string()).unwrap();
+        laptop_state.record(&laptop, 150);
+        sync(&mut laptop_state, &mut laptop, &server).await;
+        sync(&mut phone_state, &mut phone, &server).await;
+
+        assert_eq!(titles(&phone), vec!["Theirs"]);
+        // the lists now match the server, so nothing is queued again
+        phone_state.record(&phone, 200);
+        assert_eq!(phone_state.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn pull_again_once_a_list_is_added() {
+        let server = server();
+        let (mut laptop, mut laptop_state) = (Lists::default(), SyncState::default());
+        laptop.new_list(9, "Work".to_string()).unwrap();
+        laptop.find_mut(9).unwrap().todos.new_todo("Report".to_string()).unwrap();
+        laptop_state.record(&laptop, 100);
+        sync(&mut laptop_state, &mut laptop, &server).await;
+
+        // the phone doesn't have the list, so the todo is skipped
+        let (mut phone, mut phone_state) = (Lists::default(), SyncState::default());
+        phone_state.record(&phone, 200);
+        sync(&mut phone_state, &mut phone, &server).await;
+        assert_eq!(phone_state.pulled, 1);
+
+        // e.g. the list was imported from a file
+        phone.new_list(9, "Work".to_string()).unwrap();
+        phone_state.record(&phone, 300);
+        sync(&mut phone_state, &mut phone, &server).await;
+
+        let titles: Vec<&str> = phone.find(9).unwrap().todos.0.iter().map(|todo| todo.title.as_str()).collect();
+        assert_eq!(titles, vec!["Report"]);
+        phone_state.record(&phone, 400);
+        assert_eq!(phone_state.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn an_edit_after_a_pull_keeps_the_pulled_todo() {
+        let runtime = create_runtime();
+        let server = server();
+        let (mut phone, mut state) = (lists(&["A"]), SyncState::default());
+        state.record(&phone, 100);
+        sync(&mut state, &mut phone, &server).await;
+
+        let (mut laptop, mut laptop_state) = (Lists::default(), SyncState::default());
+        sync(&mut laptop_state, &mut laptop, &server).await;
+        let b = ToDo { id: 5, ..ToDo::new("B".to_string()) };
+        laptop.find_mut(0).unwrap().todos.reduce(&ToDoAction::Add(b)).unwrap();
+        laptop_state.record(&laptop, 200);
+        sync(&mut laptop_state, &mut laptop, &server).await;
+
+        // the phone has the list open. Pulled todos go straight into the
+        // lists, the same as in `flush`.
+        let (lists, set_lists) = create_signal(phone);
+        let (_, set_todos) = list_todos(0, lists, set_lists);
+        let changes = request(&server, "GET", pull_path(state.pulled), None).await;
+        set_lists.update(|lists| state.pulled(lists, changes));
+        set_todos.update(|todos| { todos.edit(0, "A2".to_string()).unwrap(); });
+
+        lists.with_untracked(|lists| {
+            assert_eq!(titles(lists), vec!["A2", "B"]);
+            state.record(lists, 300);
+        });
+        // only the edit is sent, not a delete of the pulled todo
+        assert_eq!(state.outbox.iter().map(|mutation| mutation.id).collect::<Vec<_>>(), vec![0]);
+        runtime.dispose();
+    }
+
+    #[tokio::test]
+    async fn pull_other_clients_changes() {
+        let mut lists = lists(&["A", "B"]);
+        let mut state = SyncState::default();
+        let server = server();
+        state.record(&lists, 100);
+        sync(&mut state, &mut lists, &server).await;
+
+        // B has been edited here but not sent yet
+        lists.find_mut(0).unwrap().todos.edit(1, "B2".to_string()).unwrap();
+        state.record(&lists, 200);
+
+        let record = |id: u64, title: Option<&str>, revision: u64| Record {
+            list_id: 0,
+            id,
+            revision,
+            updated_at: 150,
+            todo: title.map(|title| ToDo { id, ..ToDo::new(title.to_string()) }),
+        };
+        state.pulled(&mut lists, Changes {
+            revision: 7,
+            records: vec![
+                record(0, None, 3),
+                record(1, Some("B from elsewhere"), 4),
+                record(2, Some("C"), 5),
+                Record { list_id: 9, ..record(0, Some("Unknown list"), 6) },
+            ],
+        });
+
+        assert_eq!(titles(&lists), vec!["B2", "C"]);
+        assert_eq!(state.pulled, 7);
+        state.record(&lists, 300);
+        assert_eq!(state.outbox.iter().map(|mutation| mutation.id).collect::<Vec<_>>(), vec![1]);
+    }
+}
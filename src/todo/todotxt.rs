@@ -0,0 +1,215 @@
+use std::fmt;
+
+use super::{ date::InvalidDate, Date, Priority, Status, ToDo, ToDos };
+
+// Reads and writes the todo.txt format (https://github.com/todotxt/todo.txt),
+// one todo per line:
+//
+//     (A) 2024-05-01 Call Mom +family @phone due:2024-05-10
+//     x 2024-05-03 2024-05-01 Pay rent pri:C
+//
+// `x` marks a completed todo and `(A)` or `pri:A` is the priority. The
+// dates in front of the title are when it was completed and created. Open
+// todos are read into the backlog, so whether one was in progress is lost
+// on the way out. Both `+project` and `@context` words become tags, and tags
+// are always written back as `+project`. Sub-tasks are written as ordinary
+// lines.
+
+/// A line that couldn't be read, numbered from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineError {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+/// Write the whole list as todo.txt, in list order.
+pub fn export(todos: &ToDos) -> String {
+    todos.0.iter().map(format_line).map(|line| line + "\n").collect()
+}
+
+fn format_line(todo: &ToDo) -> String {
+    let mut words = Vec::new();
+
+    // todo.txt only allows the `(A)` form on open todos
+    let priority = match todo.priority {
+        Priority::High => Some('A'),
+        Priority::Normal => None,
+        Priority::Low => Some('C'),
+    };
+    if todo.is_completed() {
+        words.push("x".to_string());
+        // a lone date after the `x` would be read as the completion date, so
+        // the creation date can only be written along with it
+        if let Some(completed_on) = todo.completed_on {
+            words.push(completed_on.to_string());
+            words.extend(todo.created_on.map(|created_on| created_on.to_string()));
+        }
+        words.extend(priority.map(|priority| format!("pri:{priority}")));
+    } else {
+        words.extend(priority.map(|priority| format!("({priority})")));
+        words.extend(todo.created_on.map(|created_on| created_on.to_string()));
+    }
+
+    words.push(todo.title.clone());
+    words.extend(todo.tags.iter().map(|tag| format!("+{tag}")));
+    words.extend(todo.due.map(|due| format!("due:{due}")));
+    words.join(" ")
+}
+
+/// Read every line of a todo.txt file. Blank lines are skipped. The todos
+/// are numbered by line so they each have a different id.
+pub fn parse(text: &str) -> (Vec<ToDo>, Vec<LineError>) {
+    let mut todos = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(line) {
+            Ok(todo) => todos.push(ToDo { id: index as u64, ..todo }),
+            Err(reason) => errors.push(LineError { line: index + 1, reason }),
+        }
+    }
+
+    (todos, errors)
+}
+
+fn parse_line(line: &str) -> Result<ToDo, String> {
+    let mut words = line.split_whitespace().peekable();
+    let mut todo = ToDo::new(String::new());
+
+    if words.next_if_eq(&"x").is_some() {
+        todo.status = Status::Done;
+    } else if let Some(priority) = words.peek().and_then(|word| parse_priority(word.strip_prefix('(')?.strip_suffix(')')?)) {
+        words.next();
+        todo.priority = priority;
+    }
+
+    // a completed todo can have a completion date and a creation date, an
+    // open one just a creation date
+    let mut date = || -> Result<Option<Date>, String> {
+        words.next_if(|word| looks_like_date(word))
+            .map(|date| date.parse::<Date>().map_err(|err| err.to_string()))
+            .transpose()
+    };
+    if todo.is_completed() {
+        todo.completed_on = date()?;
+        if todo.completed_on.is_some() {
+            todo.created_on = date()?;
+        }
+    } else {
+        todo.created_on = date()?;
+    }
+
+    let mut title = Vec::new();
+    for word in words {
+        let tag = word.strip_prefix('+').or_else(|| word.strip_prefix('@')).filter(|tag| !tag.is_empty());
+        if let Some(tag) = tag {
+            todo.add_tags(vec![tag.to_lowercase()]);
+        } else if let Some(due) = word.strip_prefix("due:") {
+            todo.due = Some(due.parse().map_err(|err: InvalidDate| err.to_string())?);
+        } else if let Some(priority) = word.strip_prefix("pri:") {
+            todo.priority = parse_priority(priority).ok_or_else(|| format!("`{priority}` is not a priority"))?;
+        } else {
+            title.push(word);
+        }
+    }
+
+    if title.is_empty() {
+        return Err("there's no description".to_string());
+    }
+    todo.title = title.join(" ");
+    Ok(todo)
+}
+
+/// Priorities run from `A` (highest) to `Z`. `B` is treated as normal and
+/// anything below it as low.
+fn parse_priority(letter: &str) -> Option<Priority> {
+    match letter {
+        "A" => Some(Priority::High),
+        "B" => Some(Priority::Normal),
+        _ if letter.len() == 1 && letter.chars().all(|c| c.is_ascii_uppercase()) => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// Whether a word is in the `YYYY-MM-DD` shape, valid or not.
+fn looks_like_date(word: &str) -> bool {
+    word.len() == 10
+        && word.char_indices().all(|(i, c)| if i == 4 || i == 7 { c == '-' } else { c.is_ascii_digit() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_todos() {
+        let mut todos = ToDos(Vec::new());
+        todos.new_todo("Call Mom #family".to_string()).unwrap().priority = Priority::High;
+        let done = todos.new_todo("Pay rent".to_string()).unwrap();
+        done.toggle("2024-05-03".parse().unwrap());
+        done.created_on = "2024-05-01".parse().ok();
+        done.due = "2024-05-01".parse().ok();
+        let low = todos.new_todo("Tidy shed".to_string()).unwrap();
+        low.priority = Priority::Low;
+        low.toggle("2024-05-03".parse().unwrap());
+        low.completed_on = None;
+
+        assert_eq!(
+            export(&todos),
+            "(A) Call Mom +family\nx 2024-05-03 2024-05-01 Pay rent due:2024-05-01\nx pri:C Tidy shed\n"
+        );
+    }
+
+    #[test]
+    fn parse_line_parts() {
+        let todo = parse_line("(A) 2024-04-01 Call Mom +Family @phone due:2024-05-10").unwrap();
+
+        assert_eq!(todo.title, "Call Mom");
+        assert_eq!(todo.priority, Priority::High);
+        assert_eq!(todo.tags, vec!["family", "phone"]);
+        assert_eq!(todo.due, "2024-05-10".parse().ok());
+        assert_eq!(todo.created_on, "2024-04-01".parse().ok());
+        assert!(!todo.is_completed());
+    }
+
+    #[test]
+    fn parse_completed_line() {
+        let todo = parse_line("x 2024-05-02 2024-04-01 Pay rent pri:D").unwrap();
+
+        assert_eq!(todo.title, "Pay rent");
+        assert_eq!(todo.priority, Priority::Low);
+        assert_eq!(todo.completed_on, "2024-05-02".parse().ok());
+        assert_eq!(todo.created_on, "2024-04-01".parse().ok());
+        assert!(todo.is_completed());
+    }
+
+    #[test]
+    fn report_malformed_lines() {
+        let (todos, errors) = parse("Buy milk\n\nx 2024-05-02\nFile taxes due:2024-02-30\n(B) Walk dog pri:9\n");
+
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].title, "Buy milk");
+        assert_eq!(errors.iter().map(|error| error.line).collect::<Vec<_>>(), vec![3, 4, 5]);
+        assert_eq!(errors[0].to_string(), "line 3: there's no description");
+    }
+
+    #[test]
+    fn round_trip() {
+        let text = "(A) 2024-05-01 Call Mom +family due:2024-05-10\nx 2024-05-03 Pay rent\nx pri:C Tidy shed +home\n";
+        let (todos, errors) = parse(text);
+        assert!(errors.is_empty());
+
+        let mut list = ToDos(Vec::new());
+        assert!(list.import(todos, false).is_empty());
+        assert_eq!(export(&list), text);
+    }
+}